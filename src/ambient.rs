@@ -0,0 +1,161 @@
+// ambient.rs
+
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use crate::camera::sample_disk;
+use crate::offset_origin;
+use crate::ray_intersect::{Intersect, RayCount};
+use crate::scene::Scene;
+use crate::world::Cell;
+use crate::block_shape::BlockShape;
+
+// Fraction of the hemisphere above a hit that is open within `radius`, from
+// `samples` cosine-weighted rays. 1 is fully open, 0 fully enclosed.
+pub fn ambient_occlusion(intersect: &Intersect, scene: &Scene, radius: f32, samples: u32, rays: &RayCount) -> f32 {
+    let mut rng = rand::thread_rng();
+    let (tangent, bitangent) = tangent_frame(&intersect.normal);
+    rays.add(samples);
+
+    let blocked = (0..samples)
+        .filter(|_| {
+            let (x, y) = sample_disk((rng.gen(), rng.gen()));
+            let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+            let direction = (tangent * x + bitangent * y + intersect.normal * z).normalize();
+            let origin = offset_origin(intersect, &direction);
+
+            scene.objects().any(|object| {
+                let hit = object.ray_intersect(&origin, &direction);
+                hit.is_intersecting && hit.distance < radius
+            })
+        })
+        .count();
+
+    1.0 - blocked as f32 / samples.max(1) as f32
+}
+
+// Minecraft-style smooth lighting. Every corner of the face that was hit is darkened by
+// the opaque blocks next to it in front of the face, and the corners are interpolated
+// across the face. Only faces aligned with the block grid are darkened, and only
+// block hits should use it: props are not part of the grid.
+pub fn smooth_lighting(intersect: &Intersect, scene: &Scene) -> f32 {
+    let normal = intersect.normal;
+    let axis = normal.iamax();
+    if normal[axis].abs() < 0.999 {
+        return 1.0;
+    }
+
+    // Layer of cells just in front of the face, and the two axes along it
+    let front = intersect.point + normal * 0.5;
+    let (first, second) = ((axis + 1) % 3, (axis + 2) % 3);
+    let cell = |offset_first: i32, offset_second: i32| {
+        let mut cell = [front.x.floor() as i32, front.y.floor() as i32, front.z.floor() as i32];
+        cell[first] += offset_first;
+        cell[second] += offset_second;
+        is_opaque_block(scene, (cell[0], cell[1], cell[2]))
+    };
+
+    // Light of the corner towards `a` along the first axis and `b` along the second, from 0 to 1
+    let corner = |a: i32, b: i32| {
+        let (side_a, side_b, diagonal) = (cell(a, 0), cell(0, b), cell(a, b));
+        if side_a && side_b {
+            0.0
+        } else {
+            (3 - side_a as u32 - side_b as u32 - diagonal as u32) as f32 / 3.0
+        }
+    };
+
+    let u = intersect.point[first] - intersect.point[first].floor();
+    let v = intersect.point[second] - intersect.point[second].floor();
+    let near = corner(-1, -1) * (1.0 - u) + corner(1, -1) * u;
+    let far = corner(-1, 1) * (1.0 - u) + corner(1, 1) * u;
+    near * (1.0 - v) + far * v
+}
+
+// Full blocks that let no light through
+fn is_opaque_block(scene: &Scene, cell: Cell) -> bool {
+    match scene.world.get(cell) {
+        Some(block) => {
+            let material = &scene.palette[block.material as usize - 1].1;
+            block.shape == BlockShape::Full && material.albedo[3] <= 0.0
+        }
+        None => false,
+    }
+}
+
+// Two unit vectors perpendicular to `normal` and to each other
+fn tangent_frame(normal: &Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    (tangent, normal.cross(&tangent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::world::{Block, World};
+
+    const DIRT: u8 = 1;
+    const GLASS: u8 = 4;
+
+    // Light on the top face of a dirt block at the origin, at `x` and `z` within the
+    // face, with `neighbours` placed in the layer above it
+    fn top_face_light(neighbours: &[(Cell, Block)], x: f32, z: f32) -> f32 {
+        let mut world = World::new();
+        world.set((0, 0, 0), Block::new(DIRT, BlockShape::Full));
+        for (cell, block) in neighbours {
+            world.set(*cell, *block);
+        }
+        let scene = Scene::new(world, Vec::new());
+
+        let point = Vec3::new(x, 1.0, z);
+        let intersect = Intersect::new(point, Vec3::new(0.0, 1.0, 0.0), 1.0, Material::black(), x, z);
+        smooth_lighting(&intersect, &scene)
+    }
+
+    fn dirt(cell: Cell) -> (Cell, Block) {
+        (cell, Block::new(DIRT, BlockShape::Full))
+    }
+
+    #[test]
+    fn open_faces_are_fully_lit() {
+        assert_eq!(top_face_light(&[], 0.0, 0.0), 1.0);
+        assert_eq!(top_face_light(&[], 0.5, 0.5), 1.0);
+        // Blocks below the layer in front of the face do not matter
+        assert_eq!(top_face_light(&[dirt((-1, 0, 0)), dirt((0, 0, -1))], 0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn corner_loses_a_third_per_opaque_neighbour() {
+        // The corner at x = 0, z = 0 has its sides at -x and -z and the diagonal between them
+        let (side_x, side_z, diagonal) = ((-1, 1, 0), (0, 1, -1), (-1, 1, -1));
+
+        assert!((top_face_light(&[dirt(side_x)], 0.0, 0.0) - 2.0 / 3.0).abs() < 1e-6);
+        assert!((top_face_light(&[dirt(diagonal)], 0.0, 0.0) - 2.0 / 3.0).abs() < 1e-6);
+        assert!((top_face_light(&[dirt(side_z), dirt(diagonal)], 0.0, 0.0) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn corner_between_two_opaque_sides_is_dark() {
+        // Fully dark even without the diagonal block, which can't be seen past both sides
+        assert_eq!(top_face_light(&[dirt((-1, 1, 0)), dirt((0, 1, -1))], 0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn corners_are_interpolated_across_the_face() {
+        // A wall along -z darkens both corners on that edge to 2/3
+        let wall = [dirt((0, 1, -1))];
+
+        assert!((top_face_light(&wall, 0.5, 0.0) - 2.0 / 3.0).abs() < 1e-6);
+        assert!((top_face_light(&wall, 0.5, 0.5) - 5.0 / 6.0).abs() < 1e-6);
+        assert!((top_face_light(&wall, 0.5, 0.999) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn transparent_and_partial_blocks_let_light_through() {
+        let glass = ((-1, 1, 0), Block::new(GLASS, BlockShape::Full));
+        let slab = ((0, 1, -1), Block::new(DIRT, BlockShape::TopSlab));
+
+        assert_eq!(top_face_light(&[glass, slab], 0.0, 0.0), 1.0);
+    }
+}
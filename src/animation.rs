@@ -0,0 +1,137 @@
+// animation.rs
+
+use std::f32::consts::PI;
+use std::fs;
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub fov: f32, // Radians
+}
+
+// Camera path through a list of keyframes, interpolated with Catmull-Rom
+// splines so the camera goes through every keyframe without sharp turns
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keyframes }
+    }
+
+    // One keyframe per line: `time eye_x eye_y eye_z center_x center_y center_z fov_degrees`.
+    // Empty lines and lines starting with `#` are skipped.
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(file_path).map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+
+        let mut keyframes = Vec::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("{}:{}: invalid number", file_path, number + 1))?;
+            if values.len() != 8 {
+                return Err(format!("{}:{}: expected 8 values, found {}", file_path, number + 1, values.len()));
+            }
+
+            keyframes.push(Keyframe {
+                time: values[0],
+                eye: Vec3::new(values[1], values[2], values[3]),
+                center: Vec3::new(values[4], values[5], values[6]),
+                fov: values[7].to_radians(),
+            });
+        }
+
+        if keyframes.is_empty() {
+            return Err(format!("{}: no keyframes", file_path));
+        }
+
+        Ok(CameraPath::new(keyframes))
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().unwrap().time - self.keyframes[0].time
+    }
+
+    // Interpolated keyframe at `time`, clamped to the ends of the path
+    pub fn sample(&self, time: f32) -> Keyframe {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+
+        if time <= keyframes[0].time || last == 0 {
+            return keyframes[0];
+        }
+        if time >= keyframes[last].time {
+            return keyframes[last];
+        }
+
+        // Segment between keyframes i and i + 1, neighbours repeated at the ends
+        let i = keyframes.windows(2).position(|pair| time < pair[1].time).unwrap();
+        let k0 = &keyframes[i.saturating_sub(1)];
+        let k1 = &keyframes[i];
+        let k2 = &keyframes[i + 1];
+        let k3 = &keyframes[(i + 2).min(last)];
+        let t = (time - k1.time) / (k2.time - k1.time);
+
+        Keyframe {
+            time,
+            eye: catmull_rom(k0.eye, k1.eye, k2.eye, k3.eye, t),
+            center: catmull_rom(k0.center, k1.center, k2.center, k3.center, t),
+            fov: catmull_rom_scalar(k0.fov, k1.fov, k2.fov, k3.fov, t),
+        }
+    }
+
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        let keyframe = self.sample(self.keyframes[0].time + time);
+        camera.eye = keyframe.eye;
+        camera.center = keyframe.center;
+        camera.fov = keyframe.fov;
+    }
+}
+
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5
+}
+
+fn catmull_rom_scalar(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    catmull_rom(Vec3::new(p0, 0.0, 0.0), Vec3::new(p1, 0.0, 0.0), Vec3::new(p2, 0.0, 0.0), Vec3::new(p3, 0.0, 0.0), t).x
+}
+
+// Advances a turntable animation by one of `frames` equal steps around the center
+pub fn turntable_step(camera: &mut Camera, frames: usize) {
+    camera.orbit(2.0 * PI / frames as f32, 0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, x: f32) -> Keyframe {
+        Keyframe { time, eye: Vec3::new(x, 2.0, 0.0), center: Vec3::zeros(), fov: 1.0 }
+    }
+
+    #[test]
+    fn path_goes_through_its_keyframes_and_stops_at_the_ends() {
+        let path = CameraPath::new(vec![keyframe(2.0, 10.0), keyframe(0.0, 0.0), keyframe(1.0, 4.0)]);
+        assert_eq!(path.duration(), 2.0);
+
+        for (time, x) in [(-1.0, 0.0), (0.0, 0.0), (1.0, 4.0), (2.0, 10.0), (3.0, 10.0)] {
+            let sample = path.sample(time);
+            assert!((sample.eye - Vec3::new(x, 2.0, 0.0)).magnitude() < 1e-5, "{} {:?}", time, sample.eye);
+        }
+    }
+}
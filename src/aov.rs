@@ -0,0 +1,194 @@
+// aov.rs
+
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use crate::camera::Camera;
+use crate::debug_view::id_color;
+use crate::fog::apply_fog;
+use crate::light::Light;
+use crate::ray_intersect::RayCount;
+use crate::render_settings::RenderSettings;
+use crate::scene::Scene;
+use crate::{closest_hit, screen_coordinates, shade};
+
+// Arbitrary output variables: the separate images a render can be split into
+// for compositing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pass {
+    // The final image, the sum of the six passes after it seen through the fog
+    Beauty,
+    Diffuse,
+    Specular,
+    Reflection,
+    Refraction,
+    Emission,
+    // Radiance of the environment where nothing was hit
+    Environment,
+    // 1 where the light is blocked
+    Shadow,
+    Normal,
+    // Linear view-space depth, see `Camera::view_depth`, infinite where nothing was hit
+    Depth,
+    // ID of the block or prop, 0 where nothing was hit
+    ObjectId,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 11] = [
+        Pass::Beauty,
+        Pass::Diffuse,
+        Pass::Specular,
+        Pass::Reflection,
+        Pass::Refraction,
+        Pass::Emission,
+        Pass::Environment,
+        Pass::Shadow,
+        Pass::Normal,
+        Pass::Depth,
+        Pass::ObjectId,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::Beauty => "beauty",
+            Pass::Diffuse => "diffuse",
+            Pass::Specular => "specular",
+            Pass::Reflection => "reflection",
+            Pass::Refraction => "refraction",
+            Pass::Emission => "emission",
+            Pass::Environment => "environment",
+            Pass::Shadow => "shadow",
+            Pass::Normal => "normal",
+            Pass::Depth => "depth",
+            Pass::ObjectId => "object_id",
+        }
+    }
+
+    // Channel names in multi-channel files, single value passes only use x
+    pub fn channels(self) -> &'static [&'static str] {
+        match self {
+            Pass::Shadow => &["Y"],
+            Pass::Depth => &["Z"],
+            Pass::ObjectId => &["id"],
+            Pass::Normal => &["X", "Y", "Z"],
+            _ => &["R", "G", "B"],
+        }
+    }
+
+    fn index(self) -> usize {
+        Pass::ALL.iter().position(|pass| *pass == self).unwrap()
+    }
+}
+
+// Every pass of one render, as linear values
+pub struct Passes {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<[Vec3; Pass::ALL.len()]>,
+}
+
+impl Passes {
+    pub fn values(&self, pass: Pass) -> impl Iterator<Item = Vec3> + '_ {
+        let index = pass.index();
+        self.pixels.iter().map(move |pixel| pixel[index])
+    }
+
+    // The pass mapped to [0, 1] colors for an 8-bit image: normals from [-1, 1],
+    // depth from white at the camera to black at the farthest hit, and a
+    // distinct color for every object
+    pub fn display_values(&self, pass: Pass) -> Vec<Vec3> {
+        match pass {
+            Pass::Normal => self.values(pass).map(|normal| normal * 0.5 + Vec3::new(0.5, 0.5, 0.5)).collect(),
+            Pass::Depth => {
+                let far = self.values(pass).map(|depth| depth.x).filter(|d| d.is_finite()).fold(0.0, f32::max);
+                self.values(pass)
+                    .map(|depth| {
+                        if depth.x.is_finite() && far > 0.0 {
+                            Vec3::repeat((1.0 - depth.x / far).clamp(0.0, 1.0))
+                        } else {
+                            Vec3::zeros()
+                        }
+                    })
+                    .collect()
+            }
+            Pass::ObjectId => self
+                .values(pass)
+                .map(|id| match id.x as u64 {
+                    0 => Vec3::zeros(),
+                    id => id_color(id),
+                })
+                .collect(),
+            _ => self.values(pass).map(|value| value.map(|c| c.clamp(0.0, 1.0))).collect(),
+        }
+    }
+}
+
+// Renders every pass at once. The lighting passes are averaged over the samples,
+// normals, depth and IDs come from the first sample, which like in `render` goes
+// through the top left corner of the pixel.
+pub fn render_passes(
+    width: usize,
+    height: usize,
+    scene: &Scene,
+    camera: &Camera,
+    light: &Light,
+    settings: &RenderSettings,
+) -> Passes {
+    let aspect_ratio = width as f32 / height as f32;
+
+    let pixels = (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let mut rng = rand::thread_rng();
+            // Nothing shows the ray count of the passes
+            let rays = RayCount::default();
+            let mut pixel = [Vec3::zeros(); Pass::ALL.len()];
+            pixel[Pass::Depth.index()] = Vec3::repeat(f32::INFINITY);
+
+            for sample in 0..settings.samples {
+                let (offset_x, offset_y) = if sample == 0 { (0.0, 0.0) } else { (rng.gen(), rng.gen()) };
+                let (screen_x, screen_y) =
+                    screen_coordinates(x as f32 + offset_x, y as f32 + offset_y, width as f32, height as f32);
+                let (ray_origin, ray_direction) =
+                    camera.lens_ray(screen_x, screen_y, aspect_ratio, (rng.gen(), rng.gen()));
+
+                let Some((object, intersect)) = closest_hit(&ray_origin, &ray_direction, scene) else {
+                    let sky = scene.environment.sample(&ray_direction);
+                    pixel[Pass::Beauty.index()] += apply_fog(sky, &ray_origin, &ray_direction, f32::INFINITY, scene, light, &rays);
+                    pixel[Pass::Environment.index()] += sky;
+                    continue;
+                };
+
+                let shading = shade(&intersect, &ray_direction, scene, light, settings, 0, &rays);
+                let terms = [
+                    // Fog only shows in the beauty pass, the rest are the bare surface terms
+                    (Pass::Beauty, apply_fog(shading.total(), &ray_origin, &ray_direction, intersect.distance, scene, light, &rays)),
+                    (Pass::Diffuse, shading.diffuse),
+                    (Pass::Specular, shading.specular),
+                    (Pass::Reflection, shading.reflection),
+                    (Pass::Refraction, shading.refraction),
+                    (Pass::Emission, shading.emission),
+                ];
+                for (pass, color) in terms {
+                    pixel[pass.index()] += color;
+                }
+                pixel[Pass::Shadow.index()] += Vec3::repeat(shading.shadow);
+
+                if sample == 0 {
+                    pixel[Pass::Normal.index()] = intersect.normal;
+                    pixel[Pass::Depth.index()] = Vec3::repeat(camera.view_depth(&intersect.point));
+                    pixel[Pass::ObjectId.index()] = Vec3::repeat(object as f32);
+                }
+            }
+
+            for pass in &Pass::ALL[..=Pass::Shadow.index()] {
+                pixel[pass.index()] /= settings.samples as f32;
+            }
+            pixel
+        })
+        .collect();
+
+    Passes { width, height, pixels }
+}
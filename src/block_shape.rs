@@ -0,0 +1,134 @@
+// block_shape.rs
+
+use nalgebra_glm::Vec3;
+
+// North is -z, like in Minecraft
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::East, Facing::South, Facing::West];
+
+    // Cell offset (x, z) of the neighbour in this direction
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Facing::North => (0, -1),
+            Facing::East => (1, 0),
+            Facing::South => (0, 1),
+            Facing::West => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockShape {
+    Full,
+    BottomSlab,
+    TopSlab,
+    // The tall half of the stair is on the side it faces
+    Stairs(Facing),
+    Fence,
+    Pane,
+}
+
+impl BlockShape {
+    // Shape codes used by the diorama shape layers:
+    // '.' full, '_' bottom slab, '^' top slab, 'N' 'E' 'S' 'W' stairs, 'F' fence, 'P' pane
+    pub fn from_char(code: char) -> Option<BlockShape> {
+        match code {
+            '.' => Some(BlockShape::Full),
+            '_' => Some(BlockShape::BottomSlab),
+            '^' => Some(BlockShape::TopSlab),
+            'N' => Some(BlockShape::Stairs(Facing::North)),
+            'E' => Some(BlockShape::Stairs(Facing::East)),
+            'S' => Some(BlockShape::Stairs(Facing::South)),
+            'W' => Some(BlockShape::Stairs(Facing::West)),
+            'F' => Some(BlockShape::Fence),
+            'P' => Some(BlockShape::Pane),
+            _ => None,
+        }
+    }
+
+    // Inverse of `from_char`
+    pub fn to_char(self) -> char {
+        match self {
+            BlockShape::Full => '.',
+            BlockShape::BottomSlab => '_',
+            BlockShape::TopSlab => '^',
+            BlockShape::Stairs(Facing::North) => 'N',
+            BlockShape::Stairs(Facing::East) => 'E',
+            BlockShape::Stairs(Facing::South) => 'S',
+            BlockShape::Stairs(Facing::West) => 'W',
+            BlockShape::Fence => 'F',
+            BlockShape::Pane => 'P',
+        }
+    }
+
+    // Fences join other fences and panes join other panes, both join full blocks
+    pub fn connects_to(self, other: BlockShape) -> bool {
+        match self {
+            BlockShape::Fence | BlockShape::Pane => other == self || other == BlockShape::Full,
+            _ => false,
+        }
+    }
+
+    // Sub-boxes of the shape in block-local [0, 1] coordinates. `connections`
+    // holds, in `Facing::ALL` order, whether each horizontal neighbour connects.
+    pub fn boxes(self, connections: [bool; 4]) -> Vec<(Vec3, Vec3)> {
+        let px = |v: f32| v / 16.0;
+
+        match self {
+            BlockShape::Full => vec![(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0))],
+            BlockShape::BottomSlab => vec![(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.5, 1.0))],
+            BlockShape::TopSlab => vec![(Vec3::new(0.0, 0.5, 0.0), Vec3::new(1.0, 1.0, 1.0))],
+            BlockShape::Stairs(facing) => {
+                let step = match facing {
+                    Facing::North => (Vec3::new(0.0, 0.5, 0.0), Vec3::new(1.0, 1.0, 0.5)),
+                    Facing::East => (Vec3::new(0.5, 0.5, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+                    Facing::South => (Vec3::new(0.0, 0.5, 0.5), Vec3::new(1.0, 1.0, 1.0)),
+                    Facing::West => (Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.5, 1.0, 1.0)),
+                };
+                vec![(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.5, 1.0)), step]
+            }
+            BlockShape::Fence => {
+                let mut boxes = vec![(Vec3::new(px(6.0), 0.0, px(6.0)), Vec3::new(px(10.0), 1.0, px(10.0)))];
+                // Two rails towards every connected neighbour
+                for (facing, _) in Facing::ALL.iter().zip(connections).filter(|(_, c)| *c) {
+                    for (bottom, top) in [(px(6.0), px(9.0)), (px(12.0), px(15.0))] {
+                        boxes.push(arm(*facing, px(7.0), px(9.0), bottom, top, px(6.0), px(10.0)));
+                    }
+                }
+                boxes
+            }
+            BlockShape::Pane => {
+                let mut boxes = vec![(Vec3::new(px(7.0), 0.0, px(7.0)), Vec3::new(px(9.0), 1.0, px(9.0)))];
+                // A lone pane still spans the cell from west to east
+                let connections = if connections.iter().any(|c| *c) {
+                    connections
+                } else {
+                    [false, true, false, true]
+                };
+                for (facing, _) in Facing::ALL.iter().zip(connections).filter(|(_, c)| *c) {
+                    boxes.push(arm(*facing, px(7.0), px(9.0), 0.0, 1.0, px(7.0), px(9.0)));
+                }
+                boxes
+            }
+        }
+    }
+}
+
+// Box reaching from the central post (between `inner_min` and `inner_max`) to the
+// edge of the cell in the given direction, `thickness_min..thickness_max` across
+fn arm(facing: Facing, thickness_min: f32, thickness_max: f32, bottom: f32, top: f32, inner_min: f32, inner_max: f32) -> (Vec3, Vec3) {
+    match facing {
+        Facing::North => (Vec3::new(thickness_min, bottom, 0.0), Vec3::new(thickness_max, top, inner_min)),
+        Facing::East => (Vec3::new(inner_max, bottom, thickness_min), Vec3::new(1.0, top, thickness_max)),
+        Facing::South => (Vec3::new(thickness_min, bottom, inner_max), Vec3::new(thickness_max, top, 1.0)),
+        Facing::West => (Vec3::new(0.0, bottom, thickness_min), Vec3::new(inner_min, top, thickness_max)),
+    }
+}
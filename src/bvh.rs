@@ -0,0 +1,181 @@
+// bvh.rs
+
+use nalgebra_glm::Vec3;
+
+// Primitives per leaf before we stop splitting
+const MAX_LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn empty() -> Self {
+        Aabb {
+            min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub fn grow(&mut self, point: &Vec3) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // Slab test, returns the entry distance if the box is hit before `t_max`
+    pub fn hit(&self, ray_origin: &Vec3, inv_direction: &Vec3, t_max: f32) -> Option<f32> {
+        let t0 = (self.min - ray_origin).component_mul(inv_direction);
+        let t1 = (self.max - ray_origin).component_mul(inv_direction);
+
+        let t_near = t0.inf(&t1).max().max(0.0);
+        let t_far = t0.sup(&t1).min().min(t_max);
+
+        if t_near <= t_far {
+            Some(t_near)
+        } else {
+            None
+        }
+    }
+}
+
+struct BvhNode {
+    bounds: Aabb,
+    // Leaf: first index into `indices`, inner node: index of the left child
+    first: usize,
+    // Number of primitives in a leaf, 0 for inner nodes
+    count: usize,
+}
+
+// Bounding volume hierarchy over any list of primitives given their bounds.
+// It only stores indices, so the primitives themselves stay with the owner.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            indices: (0..bounds.len()).collect(),
+        };
+
+        if !bounds.is_empty() {
+            bvh.nodes.push(BvhNode { bounds: Aabb::empty(), first: 0, count: bounds.len() });
+            bvh.subdivide(0, bounds);
+        }
+
+        bvh
+    }
+
+    fn subdivide(&mut self, node_index: usize, bounds: &[Aabb]) {
+        let first = self.nodes[node_index].first;
+        let count = self.nodes[node_index].count;
+
+        let mut node_bounds = Aabb::empty();
+        let mut centroid_bounds = Aabb::empty();
+        for &index in &self.indices[first..first + count] {
+            node_bounds = node_bounds.union(&bounds[index]);
+            centroid_bounds.grow(&bounds[index].centroid());
+        }
+        self.nodes[node_index].bounds = node_bounds;
+
+        if count <= MAX_LEAF_SIZE {
+            return;
+        }
+
+        // Split along the axis where the centroids are spread the most
+        let extent = centroid_bounds.max - centroid_bounds.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+
+        if extent[axis] <= 0.0 {
+            return;
+        }
+
+        self.indices[first..first + count].sort_by(|&a, &b| {
+            bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis])
+        });
+
+        let left_count = count / 2;
+        let left = self.nodes.len();
+        self.nodes.push(BvhNode { bounds: Aabb::empty(), first, count: left_count });
+        self.nodes.push(BvhNode { bounds: Aabb::empty(), first: first + left_count, count: count - left_count });
+
+        self.nodes[node_index].first = left;
+        self.nodes[node_index].count = 0;
+
+        self.subdivide(left, bounds);
+        self.subdivide(left + 1, bounds);
+    }
+
+    // Walks the tree front to back. `hit` is called with the index of every
+    // primitive whose leaf is reached and returns its hit distance, if any.
+    pub fn traverse<F>(&self, ray_origin: &Vec3, ray_direction: &Vec3, mut hit: F)
+    where
+        F: FnMut(usize) -> Option<f32>,
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let inv_direction = Vec3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+        let mut closest = f32::INFINITY;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if node.bounds.hit(ray_origin, &inv_direction, closest).is_none() {
+                continue;
+            }
+
+            if node.count > 0 {
+                for &index in &self.indices[node.first..node.first + node.count] {
+                    if let Some(distance) = hit(index) {
+                        closest = closest.min(distance);
+                    }
+                }
+                continue;
+            }
+
+            // Visit the nearer child first so `closest` shrinks sooner
+            let left = &self.nodes[node.first];
+            let right = &self.nodes[node.first + 1];
+            let left_t = left.bounds.hit(ray_origin, &inv_direction, closest);
+            let right_t = right.bounds.hit(ray_origin, &inv_direction, closest);
+
+            match (left_t, right_t) {
+                (Some(l), Some(r)) => {
+                    if l < r {
+                        stack.push(node.first + 1);
+                        stack.push(node.first);
+                    } else {
+                        stack.push(node.first);
+                        stack.push(node.first + 1);
+                    }
+                }
+                (Some(_), None) => stack.push(node.first),
+                (None, Some(_)) => stack.push(node.first + 1),
+                (None, None) => {}
+            }
+        }
+    }
+}
@@ -2,10 +2,153 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Orthographic rays start this far behind the eye, so geometry between the eye
+// plane and the near plane is drawn instead of clipped
+const ORTHO_NEAR: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+    // Orthographic seen from the classic isometric angle
+    Isometric,
+    // Whole sphere around the eye, longitude across and latitude down the image
+    Equirectangular,
+    // One 90 degree face of a cubemap around the eye
+    Cubemap(CubeFace),
+}
+
+impl Projection {
+    // Projections the window cycles through. Panoramas need their own image shape
+    // (2:1, or six square faces) and are only rendered headless.
+    pub fn next(self) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Isometric,
+            Projection::Isometric | Projection::Equirectangular | Projection::Cubemap(_) => Projection::Perspective,
+        }
+    }
+
+    // Panoramic projections only depend on the eye, not on where the camera looks
+    pub fn is_panoramic(self) -> bool {
+        matches!(self, Projection::Equirectangular | Projection::Cubemap(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    // Short name used for the file of each face
+    pub fn name(self) -> &'static str {
+        match self {
+            CubeFace::PositiveX => "px",
+            CubeFace::NegativeX => "nx",
+            CubeFace::PositiveY => "py",
+            CubeFace::NegativeY => "ny",
+            CubeFace::PositiveZ => "pz",
+            CubeFace::NegativeZ => "nz",
+        }
+    }
+
+    // Forward and up vectors of the face. Side faces keep +y up and the top and
+    // bottom ones line up with -z, so the faces form a cross around -z.
+    fn axes(self) -> (Vec3, Vec3) {
+        match self {
+            CubeFace::PositiveX => (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            CubeFace::NegativeX => (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            CubeFace::PositiveY => (Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)),
+            CubeFace::NegativeY => (Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 0.0, -1.0)),
+            CubeFace::PositiveZ => (Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 0.0)),
+            CubeFace::NegativeZ => (Vec3::new(0.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0)),
+        }
+    }
+
+    // Direction through a point of the face, with `screen_x` and `screen_y` in [-1, 1]
+    pub fn direction(self, screen_x: f32, screen_y: f32) -> Vec3 {
+        let (forward, up) = self.axes();
+        let right = forward.cross(&up);
+        (forward + right * screen_x + up * screen_y).normalize()
+    }
+
+    // Face a direction points through, with the screen coordinates it crosses it at
+    pub fn from_direction(direction: &Vec3) -> (CubeFace, f32, f32) {
+        let abs = direction.abs();
+        let face = if abs.x >= abs.y && abs.x >= abs.z {
+            if direction.x > 0.0 { CubeFace::PositiveX } else { CubeFace::NegativeX }
+        } else if abs.y >= abs.z {
+            if direction.y > 0.0 { CubeFace::PositiveY } else { CubeFace::NegativeY }
+        } else if direction.z > 0.0 {
+            CubeFace::PositiveZ
+        } else {
+            CubeFace::NegativeZ
+        };
+
+        let (forward, up) = face.axes();
+        let right = forward.cross(&up);
+        let distance = direction.dot(&forward);
+        (face, direction.dot(&right) / distance, direction.dot(&up) / distance)
+    }
+}
+
+// Direction for a point of an equirectangular image, with `u` and `v` in [0, 1].
+// The middle of the image looks down -z and the top row is straight up.
+pub fn equirectangular_direction(u: f32, v: f32) -> Vec3 {
+    let longitude = (u - 0.5) * 2.0 * PI;
+    let latitude = (0.5 - v) * PI;
+    Vec3::new(
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+        -latitude.cos() * longitude.cos(),
+    )
+}
+
+// Point of an equirectangular image a direction maps to, the inverse of `equirectangular_direction`
+pub fn equirectangular_uv(direction: &Vec3) -> (f32, f32) {
+    let direction = direction.normalize();
+    let longitude = direction.x.atan2(-direction.z);
+    let latitude = direction.y.clamp(-1.0, 1.0).asin();
+    (longitude / (2.0 * PI) + 0.5, 0.5 - latitude / PI)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    // Rotates and zooms around `center`
+    Orbit,
+    // Moves the eye freely and turns the view around it
+    Fly,
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
     pub up: Vec3,
+    pub mode: CameraMode,
+    pub projection: Projection,
+    pub fov: f32,           // Vertical field of view in radians, for perspective
+    pub ortho_extent: f32,  // Half of the visible height in world units, for orthographic
+    pub aperture: f32,       // Lens radius, 0 keeps everything in focus
+    pub focus_distance: f32, // Distance along the view direction that is in perfect focus
+    // Horizontal offset of the image plane, in units of the plane at distance 1.
+    // Shifts the perspective frustum sideways without turning the camera.
+    frustum_shift: f32,
     has_changed: bool,
 }
 
@@ -15,14 +158,27 @@ impl Camera {
             eye,
             center,
             up,
+            mode: CameraMode::Orbit,
+            projection: Projection::Perspective,
+            fov: PI / 3.0,
+            ortho_extent: 6.0,
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            frustum_shift: 0.0,
             has_changed: true,
         }
     }
 
-    pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
+    // Forward, right and up vectors of the camera
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
         let up = right.cross(&forward).normalize();
+        (forward, right, up)
+    }
+
+    pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
+        let (forward, right, up) = self.basis();
 
         let rotated = 
         vector.x * right +
@@ -33,6 +189,10 @@ impl Camera {
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        // The isometric elevation is fixed, tilting away from it would make it a plain
+        // orthographic view. Turning around the vertical axis keeps it isometric.
+        let delta_pitch = if self.projection == Projection::Isometric { 0.0 } else { delta_pitch };
+
         let radius_vector = self.eye - self.center;
         let radius = radius_vector.magnitude();
 
@@ -54,12 +214,157 @@ impl Camera {
         self.has_changed = true;
     }
 
+    // Origin and direction of the ray through a point of the screen, with
+    // `screen_x` and `screen_y` in [-1, 1] and y pointing up
+    pub fn primary_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> (Vec3, Vec3) {
+        match self.projection {
+            Projection::Perspective => {
+                let scale = (self.fov * 0.5).tan();
+                let direction = Vec3::new(screen_x * aspect_ratio * scale + self.frustum_shift, screen_y * scale, -1.0).normalize();
+                (self.eye, self.basis_change(&direction))
+            }
+            Projection::Orthographic | Projection::Isometric => {
+                let (forward, right, up) = self.basis();
+                let origin = self.eye - forward * ORTHO_NEAR
+                    + right * (screen_x * aspect_ratio * self.ortho_extent)
+                    + up * (screen_y * self.ortho_extent);
+                (origin, forward)
+            }
+            Projection::Equirectangular => {
+                let direction = equirectangular_direction((screen_x + 1.0) * 0.5, (1.0 - screen_y) * 0.5);
+                (self.eye, direction)
+            }
+            Projection::Cubemap(face) => (self.eye, face.direction(screen_x, screen_y)),
+        }
+    }
+
+    // Thin lens version of `primary_ray`: the ray leaves from a point of the lens,
+    // given by `lens_sample` in [0, 1)^2, and goes through the point of the focus
+    // plane the pinhole ray would reach
+    pub fn lens_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_sample: (f32, f32)) -> (Vec3, Vec3) {
+        let (origin, direction) = self.primary_ray(screen_x, screen_y, aspect_ratio);
+        if self.aperture <= 0.0 || self.projection.is_panoramic() {
+            return (origin, direction);
+        }
+
+        // The focus plane is measured from the eye, orthographic rays start behind it
+        let (forward, right, up) = self.basis();
+        let focus_depth = self.focus_distance - (origin - self.eye).dot(&forward);
+        let focus_point = origin + direction * (focus_depth / direction.dot(&forward));
+
+        let (lens_x, lens_y) = sample_disk(lens_sample);
+        let lens_origin = origin + right * (lens_x * self.aperture) + up * (lens_y * self.aperture);
+
+        (lens_origin, (focus_point - lens_origin).normalize())
+    }
+
+    // Copy of the camera moved `offset` along its right vector, still looking parallel
+    // to it. The frustum is shifted instead of turning the eye (toe-in), so both eyes
+    // share the image plane and the point `convergence` ahead shows at the screen center.
+    pub fn stereo_eye(&self, offset: f32, convergence: f32) -> Camera {
+        let right = self.basis_change(&Vec3::new(1.0, 0.0, 0.0));
+
+        let mut camera = self.clone();
+        camera.eye = self.eye + right * offset;
+        camera.center = self.center + right * offset;
+        camera.frustum_shift = self.frustum_shift - offset / convergence.max(0.01);
+        camera
+    }
+
+    // Linear view-space depth of a point, measured along the view direction from the
+    // eye like a z-buffer. Panoramas look every way at once and use the distance instead.
+    pub fn view_depth(&self, point: &Vec3) -> f32 {
+        if self.projection.is_panoramic() {
+            return (point - self.eye).magnitude();
+        }
+        let (forward, _, _) = self.basis();
+        (point - self.eye).dot(&forward)
+    }
+
+    // Moves the focus plane to a point
+    pub fn focus_on(&mut self, point: &Vec3) {
+        let forward = (self.center - self.eye).normalize();
+        self.focus_distance = (point - self.eye).dot(&forward).max(0.01);
+        self.has_changed = true;
+    }
+
+    pub fn change_aperture(&mut self, delta: f32) {
+        self.aperture = (self.aperture + delta).clamp(0.0, 2.0);
+        self.has_changed = true;
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+
+        // Look down the diagonal of the cube, 45 degrees around and ~35.26 degrees down
+        if projection == Projection::Isometric {
+            let radius = (self.eye - self.center).magnitude();
+            let direction = Vec3::new(1.0, 1.0, 1.0).normalize();
+            self.eye = self.center + direction * radius;
+        }
+
+        self.has_changed = true;
+    }
+
+    // Widens or narrows the view, the field of view in perspective and the extent in orthographic
+    pub fn change_view_size(&mut self, factor: f32) {
+        match self.projection {
+            Projection::Perspective => self.fov = (self.fov * factor).clamp(PI / 18.0, PI * 0.9),
+            Projection::Orthographic | Projection::Isometric => {
+                self.ortho_extent = (self.ortho_extent * factor).clamp(0.5, 50.0)
+            }
+            // Panoramas always cover the whole sphere
+            Projection::Equirectangular | Projection::Cubemap(_) => {}
+        }
+        self.has_changed = true;
+    }
+
     pub fn zoom(&mut self, delta: f32) {
         let direction = (self.center - self.eye).normalize();
         self.eye += direction * delta;
         self.has_changed = true;
     }
 
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+    }
+
+    // Moves eye and center together along the camera axes
+    pub fn fly(&mut self, forward_amount: f32, right_amount: f32, up_amount: f32) {
+        let (forward, right, _) = self.basis();
+        let offset = forward * forward_amount + right * right_amount + self.up * up_amount;
+
+        self.eye += offset;
+        self.center += offset;
+        self.has_changed = true;
+    }
+
+    // Turns the view around the eye, keeping the distance to the center
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        if self.projection == Projection::Isometric {
+            return;
+        }
+
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+
+        let current_yaw = view.z.atan2(view.x);
+        let current_pitch = (view.y / distance).asin();
+
+        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
+        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+
+        self.center = self.eye + Vec3::new(
+            distance * new_yaw.cos() * new_pitch.cos(),
+            distance * new_pitch.sin(),
+            distance * new_yaw.sin() * new_pitch.cos()
+        );
+        self.has_changed = true;
+    }
+
     pub fn is_changed(&mut self) -> bool {
         if self.has_changed {
             self.has_changed = false;
@@ -67,4 +372,60 @@ impl Camera {
         }
         false
     }
-}
\ No newline at end of file
+}
+
+// Concentric mapping of the unit square onto the unit disk, keeps samples evenly spread
+pub fn sample_disk((a, b): (f32, f32)) -> (f32, f32) {
+    let (a, b) = (2.0 * a - 1.0, 2.0 * b - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (radius, theta) = if a.abs() > b.abs() {
+        (a, (PI / 4.0) * (b / a))
+    } else {
+        (b, PI / 2.0 - (PI / 4.0) * (a / b))
+    };
+
+    (radius * theta.cos(), radius * theta.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_eyes_stay_parallel_and_meet_at_the_convergence_distance() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        let convergence_point = Vec3::new(0.0, 0.0, 4.0);
+
+        for offset in [-0.3, 0.3] {
+            let eye = camera.stereo_eye(offset, 6.0);
+            assert_eq!(eye.center - eye.eye, camera.center - camera.eye);
+
+            // The screen center of each eye looks at the same point
+            let (origin, direction) = eye.primary_ray(0.0, 0.0, 1.5);
+            let t = (convergence_point.z - origin.z) / direction.z;
+            assert!((origin + direction * t - convergence_point).magnitude() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn view_depth_is_the_same_across_a_wall_facing_the_camera() {
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+
+        assert!((camera.view_depth(&Vec3::new(0.0, 0.0, 2.0)) - 8.0).abs() < 1e-5);
+        assert!((camera.view_depth(&Vec3::new(5.0, -3.0, 2.0)) - 8.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn isometric_orbit_turns_but_keeps_the_elevation() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        camera.set_projection(Projection::Isometric);
+        let before = camera.eye;
+
+        camera.orbit(PI / 2.0, 0.3);
+        assert!((camera.eye - before).magnitude() > 1.0);
+        assert!((camera.eye.y - before.y).abs() < 1e-4, "{:?} {:?}", before, camera.eye);
+    }
+}
@@ -0,0 +1,286 @@
+// cli.rs
+
+use std::env;
+use nalgebra_glm::Vec3;
+use crate::camera::{CubeFace, Projection};
+use crate::export::Precision;
+use crate::fog::Fog;
+use crate::light::LightKind;
+use crate::render_settings::{Ambient, RenderMode, Stereo};
+
+const USAGE: &str = "\
+Usage: Graficas_Proy2 [options]
+
+  --scene <1|2>            Diorama to load (default 1)
+  --world <file>           Blocks saved with F5, replacing those of the diorama
+  --environment <path>     Equirectangular image (.hdr, .exr, .png...) or directory
+                           with the cubemap faces px, nx, py, ny, pz and nz seen
+                           by rays that miss the scene
+  --env-rotation <deg>     Rotation of the environment around the vertical axis
+  --env-intensity <f>      Multiplier for the environment radiance (default 1)
+  --ibl <samples>          Light diffuse surfaces with this many directions
+                           importance sampled from the environment (default 0)
+  --sky <hour>             Procedural daylight sky with the sun at this hour (0-24),
+                           which also moves the light to the sun unless --light
+                           is given (instead of --environment)
+  --turbidity <t>          Haze of the sky, from 2 (clear) to 10 (default 3)
+  --light <type>           point (default), directional (sun from one side) or
+                           spot (cone pointing down on the beacon)
+  --attenuation <a>        Inverse-square falloff of point and spot lights: the
+                           light is divided by 1 + a * distance² (default 0)
+  --fog <density>          Volumetric fog with this extinction per unit, lit by
+                           the light with shadows (G toggles it in the window)
+  --fog-height <falloff>   Thin the fog out with height above --fog-base, 0 keeps
+                           it homogeneous (default 0)
+  --fog-base <y>           Height the fog starts thinning out at (default 0)
+  --fog-color <r,g,b>      Color of the light scattered by the fog, 0 to 1
+  --fog-anisotropy <g>     -1 scatters back towards the light, 1 forward (default 0.3)
+  --fog-steps <n>          Samples along every ray through the fog (default 16)
+  --projection <name>      perspective, orthographic, isometric, or headless only
+                           equirectangular (use a 2:1 size) or cubemap (six
+                           square faces)
+  --samples <n>            Rays per pixel
+  --mode <name>            shaded, or a debug view: normal, depth, uv, albedo,
+                           material or cost (rays traced per pixel)
+  --ambient <mode>         Ambient light: off, occlusion (ray traced) or smooth
+                           (corners darkened by the neighbouring blocks)
+  --ambient-intensity <f>  Strength of the ambient light on an open surface
+                           (default 0.3)
+  --ao-radius <d>          Distance occluders are searched within (default 1)
+  --ao-samples <n>         Rays traced per hit for occlusion (default 8)
+  --stereo <mode>          off, anaglyph (red/cyan) or side-by-side
+  --interocular <d>        Distance between the eyes in world units
+  --convergence <d>        Distance where both eyes meet, at screen depth
+  --width <px>             Render width, or initial window width (default 500)
+  --height <px>            Render height, or initial window height (default 350)
+  --scale <factor>         Window render scale from 0.25 to 2 (default 1)
+  --target-fps <n>         Frame rate the window keeps while the camera moves by
+                           lowering the resolution, 0 to disable (default 30)
+
+Headless rendering, no window is opened:
+  --headless               Render to files and exit
+  --output <path>          Directory for a PNG sequence or cubemap faces, or a
+                           .gif/.png file. Stills can also be saved as linear
+                           .exr (with depth in Z) or Radiance .hdr (default
+                           render.png, frames for animations, cubemap for
+                           cubemaps and passes for --aov)
+  --half                   Store .exr channels as 16-bit half floats
+  --aov                    Write every render pass (beauty, diffuse, specular,
+                           reflection, refraction, emission, environment, shadow,
+                           normal, depth, object_id) to a directory of PNGs or as
+                           extra channels of an .exr file
+  --day-cycle <frames>     Move the sun of --sky through a whole day in this many
+                           frames, alone or with --turntable or --path
+  --turntable <frames>     Orbit once around the scene in this many frames
+  --path <file>            Follow the camera keyframes in this file
+  --frames <n>             Frames rendered along --path (default 60)
+  --fps <n>                Frame rate of the animated GIF (default 20)
+  --help                   Show this message";
+
+pub struct Options {
+    pub scene: u32,
+    pub world: Option<String>,
+    pub environment: Option<String>,
+    pub environment_rotation: f32,
+    pub environment_intensity: f32,
+    pub ibl_samples: u32,
+    pub sky_time: Option<f32>,
+    pub turbidity: f32,
+    // None keeps the first preset, or the sun of --sky
+    pub light: Option<LightKind>,
+    pub attenuation: f32,
+    pub fog: Fog,
+    pub projection: Projection,
+    pub samples: u32,
+    pub mode: RenderMode,
+    pub ambient: Ambient,
+    pub ambient_intensity: Option<f32>,
+    pub ao_radius: Option<f32>,
+    pub ao_samples: Option<u32>,
+    pub stereo: Stereo,
+    pub interocular_distance: Option<f32>,
+    pub convergence_distance: Option<f32>,
+    pub width: usize,
+    pub height: usize,
+    pub scale: f32,
+    pub target_fps: f32,
+    pub headless: bool,
+    pub output: String,
+    pub precision: Precision,
+    pub aov: bool,
+    pub turntable: Option<usize>,
+    pub day_cycle: Option<usize>,
+    pub path: Option<String>,
+    pub frames: usize,
+    pub fps: u32,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Options, String> {
+        let mut options = Options {
+            scene: 1,
+            world: None,
+            environment: None,
+            environment_rotation: 0.0,
+            environment_intensity: 1.0,
+            ibl_samples: 0,
+            sky_time: None,
+            turbidity: 3.0,
+            light: None,
+            attenuation: 0.0,
+            fog: Fog::default(),
+            projection: Projection::Perspective,
+            samples: 1,
+            mode: RenderMode::Shaded,
+            ambient: Ambient::Off,
+            ambient_intensity: None,
+            ao_radius: None,
+            ao_samples: None,
+            stereo: Stereo::Off,
+            interocular_distance: None,
+            convergence_distance: None,
+            width: 500,
+            height: 350,
+            scale: 1.0,
+            target_fps: 30.0,
+            headless: false,
+            output: String::new(),
+            precision: Precision::Float,
+            aov: false,
+            turntable: None,
+            day_cycle: None,
+            path: None,
+            frames: 60,
+            fps: 20,
+        };
+
+        let mut output = None;
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+            match arg.as_str() {
+                "--scene" => options.scene = parse(&value()?)?,
+                "--world" => options.world = Some(value()?),
+                "--environment" => options.environment = Some(value()?),
+                "--env-rotation" => options.environment_rotation = parse(&value()?)?,
+                "--env-intensity" => options.environment_intensity = parse(&value()?)?,
+                "--ibl" => options.ibl_samples = parse(&value()?)?,
+                "--sky" => options.sky_time = Some(parse(&value()?)?),
+                "--turbidity" => options.turbidity = parse(&value()?)?,
+                "--light" => {
+                    let name = value()?;
+                    options.light = Some(LightKind::from_name(&name).ok_or(format!("Unknown light type: {}", name))?);
+                }
+                "--attenuation" => options.attenuation = parse(&value()?)?,
+                "--fog" => {
+                    options.fog.enabled = true;
+                    options.fog.density = parse(&value()?)?;
+                }
+                "--fog-height" => options.fog.height_falloff = parse(&value()?)?,
+                "--fog-base" => options.fog.base_height = parse(&value()?)?,
+                "--fog-color" => options.fog.color = parse_color(&value()?)?,
+                "--fog-anisotropy" => options.fog.anisotropy = parse::<f32>(&value()?)?.clamp(-0.99, 0.99),
+                "--fog-steps" => options.fog.steps = parse::<u32>(&value()?)?.max(1),
+                "--projection" => {
+                    options.projection = match value()?.as_str() {
+                        "perspective" => Projection::Perspective,
+                        "orthographic" => Projection::Orthographic,
+                        "isometric" => Projection::Isometric,
+                        "equirectangular" => Projection::Equirectangular,
+                        "cubemap" => Projection::Cubemap(CubeFace::PositiveX),
+                        other => return Err(format!("Unknown projection: {}", other)),
+                    }
+                }
+                "--samples" => options.samples = parse(&value()?)?,
+                "--mode" => {
+                    let name = value()?;
+                    options.mode = RenderMode::from_name(&name).ok_or(format!("Unknown render mode: {}", name))?;
+                }
+                "--ambient" => {
+                    let name = value()?;
+                    options.ambient = Ambient::from_name(&name).ok_or(format!("Unknown ambient mode: {}", name))?;
+                }
+                "--ambient-intensity" => options.ambient_intensity = Some(parse(&value()?)?),
+                "--ao-radius" => options.ao_radius = Some(parse(&value()?)?),
+                "--ao-samples" => options.ao_samples = Some(parse(&value()?)?),
+                "--stereo" => {
+                    options.stereo = match value()?.as_str() {
+                        "off" => Stereo::Off,
+                        "anaglyph" => Stereo::Anaglyph,
+                        "side-by-side" => Stereo::SideBySide,
+                        other => return Err(format!("Unknown stereo mode: {}", other)),
+                    }
+                }
+                "--interocular" => options.interocular_distance = Some(parse(&value()?)?),
+                "--convergence" => options.convergence_distance = Some(parse(&value()?)?),
+                "--width" => options.width = parse(&value()?)?,
+                "--height" => options.height = parse(&value()?)?,
+                "--scale" => options.scale = parse::<f32>(&value()?)?.clamp(0.25, 2.0),
+                "--target-fps" => options.target_fps = parse(&value()?)?,
+                "--headless" => options.headless = true,
+                "--output" => output = Some(value()?),
+                "--half" => options.precision = Precision::Half,
+                "--aov" => options.aov = true,
+                "--turntable" => options.turntable = Some(parse(&value()?)?),
+                "--day-cycle" => options.day_cycle = Some(parse(&value()?)?),
+                "--path" => options.path = Some(value()?),
+                "--frames" => options.frames = parse(&value()?)?,
+                "--fps" => options.fps = parse(&value()?)?,
+                "--help" => return Err(USAGE.to_string()),
+                other => return Err(format!("Unknown option: {}\n\n{}", other, USAGE)),
+            }
+        }
+
+        if options.width == 0 || options.height == 0 {
+            return Err(format!("Invalid size {}x{}, width and height must be at least 1", options.width, options.height));
+        }
+        // The window renders at `--scale` of its size
+        let scaled = |size: usize| (size as f32 * options.scale) as usize;
+        if !options.headless && (scaled(options.width) == 0 || scaled(options.height) == 0) {
+            return Err(format!(
+                "Size {}x{} at scale {} leaves no pixels to render",
+                options.width, options.height, options.scale
+            ));
+        }
+
+        if options.environment.is_some() && options.sky_time.is_some() {
+            return Err(String::from("--environment and --sky both set what rays that miss the scene see, use only one"));
+        }
+
+        if options.aov && matches!(options.projection, Projection::Cubemap(_)) {
+            return Err(String::from("Render passes are not written for cubemaps, drop --aov or use another projection"));
+        }
+
+        if options.projection.is_panoramic() && !options.headless {
+            return Err(String::from("Equirectangular and cubemap projections are headless only, add --headless"));
+        }
+
+        // Animations and cubemaps need a directory, or a GIF for animations
+        let animated = options.turntable.is_some() || options.path.is_some() || options.day_cycle.is_some();
+        options.output = output.unwrap_or_else(|| {
+            let default = match options.projection {
+                Projection::Cubemap(_) => "cubemap",
+                _ if animated => "frames",
+                _ if options.aov => "passes",
+                _ => "render.png",
+            };
+            String::from(default)
+        });
+
+        Ok(options)
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value: {}", value))
+}
+
+// Color as three comma separated numbers, like `0.8,0.9,1`
+fn parse_color(value: &str) -> Result<Vec3, String> {
+    let channels: Vec<f32> = value.split(',').map(|channel| parse(channel.trim())).collect::<Result<_, _>>()?;
+    match channels[..] {
+        [r, g, b] => Ok(Vec3::new(r, g, b)),
+        _ => Err(format!("Invalid color: {}", value)),
+    }
+}
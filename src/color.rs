@@ -29,7 +29,7 @@ impl Color {
     }
 
     // Function to return the color as a hex value
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 
@@ -39,7 +39,7 @@ impl Color {
     }
 
     /// Convert the color to an f32 representation
-    pub fn to_f32(&self) -> Color {
+    pub fn to_f32(self) -> Color {
         Color {
             r: (self.r as f32 / 255.0) as u8,
             g: (self.g as f32 / 255.0) as u8,
@@ -48,7 +48,7 @@ impl Color {
     }
 
     // Convert to a Vec3 representation
-    pub fn to_vec3(&self) -> Vec3 {
+    pub fn to_vec3(self) -> Vec3 {
         Vec3::new(
             self.r as f32 / 255.0,
             self.g as f32 / 255.0,
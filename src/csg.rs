@@ -0,0 +1,154 @@
+// csg.rs
+
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, inside_left: bool, inside_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => inside_left || inside_right,
+            CsgOperation::Intersection => inside_left && inside_right,
+            CsgOperation::Difference => inside_left && !inside_right,
+        }
+    }
+}
+
+// Constructive solid geometry node. Both operands must be solids, which the
+// constructors check, and nodes can be nested to build more complex shapes.
+// Every surface keeps the material of the operand it comes from.
+pub struct Csg {
+    operation: CsgOperation,
+    left: Box<dyn RayIntersect>,
+    right: Box<dyn RayIntersect>,
+}
+
+impl Csg {
+    pub fn union(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Result<Self, String> {
+        Csg::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Result<Self, String> {
+        Csg::new(CsgOperation::Intersection, left, right)
+    }
+
+    // Carves `right` out of `left`
+    pub fn difference(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Result<Self, String> {
+        Csg::new(CsgOperation::Difference, left, right)
+    }
+
+    // Open surfaces have no inside to combine
+    fn new(operation: CsgOperation, left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Result<Self, String> {
+        if !left.is_solid() || !right.is_solid() {
+            return Err(format!("CSG {:?} needs two solids, open surfaces can't be combined", operation));
+        }
+        Ok(Csg { operation, left, right })
+    }
+}
+
+impl RayIntersect for Csg {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        for span in self.ray_spans(ray_origin, ray_direction) {
+            if span.enter.distance > 0.0 {
+                return span.enter;
+            }
+            // The origin is inside the solid, so the first surface is the way out
+            if span.exit.distance > 0.0 {
+                return span.exit;
+            }
+        }
+
+        Intersect::empty()
+    }
+
+    fn is_solid(&self) -> bool {
+        true
+    }
+
+    // Sweeps the boundaries of both span lists in order, keeping track of being
+    // inside each operand, and emits a boundary whenever the combined state flips
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        let mut events: Vec<(Intersect, bool, bool)> = Vec::new();
+        for (is_left, spans) in [
+            (true, self.left.ray_spans(ray_origin, ray_direction)),
+            (false, self.right.ray_spans(ray_origin, ray_direction)),
+        ] {
+            for span in spans {
+                events.push((span.enter, is_left, true));
+                events.push((span.exit, is_left, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.distance.total_cmp(&b.0.distance));
+
+        let mut spans = Vec::new();
+        let (mut inside_left, mut inside_right, mut inside) = (false, false, false);
+        let mut enter: Option<Intersect> = None;
+
+        for (mut intersect, is_left, entering) in events {
+            if is_left {
+                inside_left = entering;
+            } else {
+                inside_right = entering;
+            }
+
+            let now_inside = self.operation.contains(inside_left, inside_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // Surfaces of a carved out operand face the other way in the result
+            if self.operation == CsgOperation::Difference && !is_left {
+                intersect.normal = -intersect.normal;
+            }
+
+            if inside {
+                enter = Some(intersect);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span::new(enter, intersect));
+            }
+        }
+
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+    use crate::plane::Plane;
+
+    fn cube(min: f32, max: f32) -> Box<dyn RayIntersect> {
+        Box::new(Cube::new(Vec3::repeat(min), Vec3::repeat(max), Material::black()))
+    }
+
+    #[test]
+    fn open_surfaces_are_rejected() {
+        let plane = Box::new(Plane::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), Material::black()));
+        assert!(Csg::difference(cube(0.0, 1.0), plane).is_err());
+        assert!(Csg::union(cube(0.0, 1.0), cube(2.0, 3.0)).is_ok());
+    }
+
+    #[test]
+    fn difference_exposes_the_carved_surface() {
+        // A cube with its middle carved out, crossed along x
+        let csg = Csg::difference(cube(0.0, 3.0), cube(1.0, 2.0)).unwrap();
+        let spans = csg.ray_spans(&Vec3::new(-1.0, 1.5, 1.5), &Vec3::new(1.0, 0.0, 0.0));
+
+        let distances: Vec<(f32, f32)> = spans.iter().map(|span| (span.enter.distance, span.exit.distance)).collect();
+        assert_eq!(distances, vec![(1.0, 2.0), (3.0, 4.0)]);
+        // The wall of the hole faces into it
+        assert_eq!(spans[0].exit.normal, Vec3::new(1.0, 0.0, 0.0));
+
+        let intersect = csg.ray_intersect(&Vec3::new(1.5, 1.5, 1.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert!((intersect.distance - 0.5).abs() < 1e-5);
+    }
+}
@@ -1,18 +1,31 @@
 // cube.rs
 
+use std::sync::Arc;
 use nalgebra_glm::Vec3;
-use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
 use crate::material::Material;
 
 pub struct Cube {
     pub min: Vec3,
     pub max: Vec3,
-    pub material: Material,
+    // Shared, the blocks of a world all point to the materials of its palette
+    pub material: Arc<Material>,
+    // Box the texture is stretched over, usually the cube itself
+    pub uv_min: Vec3,
+    pub uv_max: Vec3,
 }
 
 impl Cube {
-    pub fn new(min: Vec3, max: Vec3, material: Material) -> Self {
-        Cube { min, max, material }
+    pub fn new(min: Vec3, max: Vec3, material: impl Into<Arc<Material>>) -> Self {
+        Cube { min, max, material: material.into(), uv_min: min, uv_max: max }
+    }
+
+    // Maps the texture over a larger box, so a part of a block (slab, stair step...)
+    // shows the matching part of the block texture instead of a squashed copy
+    pub fn with_uv_bounds(mut self, uv_min: Vec3, uv_max: Vec3) -> Self {
+        self.uv_min = uv_min;
+        self.uv_max = uv_max;
+        self
     }
 
     fn get_normal(&self, hit_point: &Vec3) -> Vec3 {
@@ -37,8 +50,8 @@ impl Cube {
 
 	// Calculate UV coordinates for each face
     fn get_uv(&self, point: &Vec3) -> (f32, f32) {
-        let size = self.max - self.min; // Size of the cube
-        let local_point = (point - self.min).component_div(&size); // Normalize point to [0, 1]
+        let size = self.uv_max - self.uv_min; // Size of the texture box
+        let local_point = (point - self.uv_min).component_div(&size); // Normalize point to [0, 1]
 
         if (point.x - self.min.x).abs() < 1e-4 { // Left face
             (local_point.z, local_point.y)
@@ -54,26 +67,15 @@ impl Cube {
             (local_point.x, local_point.y)
         }
     }
-}
-
-impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        let mut t_min = (self.min.x - ray_origin.x) / ray_direction.x;
-        let mut t_max = (self.max.x - ray_origin.x) / ray_direction.x;
 
-        if t_min > t_max {
-            std::mem::swap(&mut t_min, &mut t_max);
-        }
+    // Distances where the ray enters and leaves the box, even behind the origin
+    fn slab_distances(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let (mut t_min, mut t_max) = axis_span(self.min.x, self.max.x, ray_origin.x, ray_direction.x);
 
-        let mut t_ymin = (self.min.y - ray_origin.y) / ray_direction.y;
-        let mut t_ymax = (self.max.y - ray_origin.y) / ray_direction.y;
-
-        if t_ymin > t_ymax {
-            std::mem::swap(&mut t_ymin, &mut t_ymax);
-        }
+        let (t_ymin, t_ymax) = axis_span(self.min.y, self.max.y, ray_origin.y, ray_direction.y);
 
         if (t_min > t_ymax) || (t_ymin > t_max) {
-            return Intersect::empty();
+            return None;
         }
 
         if t_ymin > t_min {
@@ -83,26 +85,24 @@ impl RayIntersect for Cube {
             t_max = t_ymax;
         }
 
-        let mut t_zmin = (self.min.z - ray_origin.z) / ray_direction.z;
-        let mut t_zmax = (self.max.z - ray_origin.z) / ray_direction.z;
-
-        if t_zmin > t_zmax {
-            std::mem::swap(&mut t_zmin, &mut t_zmax);
-        }
+        let (t_zmin, t_zmax) = axis_span(self.min.z, self.max.z, ray_origin.z, ray_direction.z);
 
         if (t_min > t_zmax) || (t_zmin > t_max) {
-            return Intersect::empty();
+            return None;
         }
 
         if t_zmin > t_min {
             t_min = t_zmin;
         }
-
-        if t_min < 0.0 {
-            return Intersect::empty();
+        if t_zmax < t_max {
+            t_max = t_zmax;
         }
 
-        let hit_point = ray_origin + ray_direction * t_min;
+        Some((t_min, t_max))
+    }
+
+    fn intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, distance: f32) -> Intersect {
+        let hit_point = ray_origin + ray_direction * distance;
         let geometric_normal = self.get_normal(&hit_point); // Normal at the hit point
 
         // Use get_uv to calculate texture coordinates
@@ -111,10 +111,51 @@ impl RayIntersect for Cube {
         Intersect::new(
             hit_point,
             geometric_normal,
-            t_min,
-            self.material.clone(),
+            distance,
+            (*self.material).clone(),
             u, // Pass u
             v, // Pass v
         )
     }
-}
\ No newline at end of file
+}
+
+impl RayIntersect for Cube {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let Some((t_min, _)) = self.slab_distances(ray_origin, ray_direction) else {
+            return Intersect::empty();
+        };
+
+        if t_min < 0.0 {
+            return Intersect::empty();
+        }
+
+        self.intersect_at(ray_origin, ray_direction, t_min)
+    }
+
+    fn is_solid(&self) -> bool {
+        true
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        match self.slab_distances(ray_origin, ray_direction) {
+            Some((t_min, t_max)) => vec![Span::new(
+                self.intersect_at(ray_origin, ray_direction, t_min),
+                self.intersect_at(ray_origin, ray_direction, t_max),
+            )],
+            None => Vec::new(),
+        }
+    }
+}
+
+// Entry and exit distances through one pair of parallel faces. A ray running
+// exactly along a face divides 0 by 0, so it is treated as inside that slab.
+fn axis_span(min: f32, max: f32, origin: f32, direction: f32) -> (f32, f32) {
+    let t0 = (min - origin) / direction;
+    let t1 = (max - origin) / direction;
+
+    if t0.is_nan() || t1.is_nan() {
+        (f32::NEG_INFINITY, f32::INFINITY)
+    } else {
+        (t0.min(t1), t0.max(t1))
+    }
+}
@@ -0,0 +1,78 @@
+// cylinder.rs
+
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{first_hit, spans_from_hits, Intersect, RayIntersect, Span, SurfaceHit};
+use crate::material::Material;
+use crate::polynomial::solve_quadratic;
+
+// Capped cylinder standing on `base` along +y. Use an `Instance` to tilt it.
+pub struct Cylinder {
+    pub base: Vec3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Material,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, radius: f32, height: f32, material: Material) -> Self {
+        Cylinder { base, radius, height, material }
+    }
+
+    // Every crossing of the surface along the ray, including those behind the origin
+    pub fn hits(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<SurfaceHit> {
+        let o = ray_origin - self.base;
+        let d = ray_direction;
+        let mut hits = Vec::new();
+
+        // Side: x^2 + z^2 = r^2 with 0 <= y <= height
+        let a = (d.x * d.x + d.z * d.z) as f64;
+        let b = 2.0 * (o.x * d.x + o.z * d.z) as f64;
+        let c = (o.x * o.x + o.z * o.z - self.radius * self.radius) as f64;
+        for t in solve_quadratic(a, b, c) {
+            let t = t as f32;
+            let p = o + d * t;
+            if p.y >= 0.0 && p.y <= self.height {
+                let u = 0.5 + p.z.atan2(p.x) / (2.0 * PI);
+                hits.push(SurfaceHit::new(t, Vec3::new(p.x, 0.0, p.z).normalize(), u, p.y / self.height));
+            }
+        }
+
+        // Caps
+        if d.y.abs() > 1e-6 {
+            for (cap_y, normal_y) in [(0.0, -1.0), (self.height, 1.0)] {
+                let t = (cap_y - o.y) / d.y;
+                let p = o + d * t;
+                if p.x * p.x + p.z * p.z <= self.radius * self.radius {
+                    let (u, v) = cap_uv(&p, self.radius);
+                    hits.push(SurfaceHit::new(t, Vec3::new(0.0, normal_y, 0.0), u, v));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+}
+
+// Flat mapping of a disc of the given radius onto [0, 1]
+pub fn cap_uv(point: &Vec3, radius: f32) -> (f32, f32) {
+    (point.x / (2.0 * radius) + 0.5, point.z / (2.0 * radius) + 0.5)
+}
+
+impl RayIntersect for Cylinder {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match first_hit(&self.hits(ray_origin, ray_direction)) {
+            Some(hit) => hit.to_intersect(ray_origin, ray_direction, &self.material),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn is_solid(&self) -> bool {
+        true
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        spans_from_hits(&self.hits(ray_origin, ray_direction), ray_origin, ray_direction, &self.material)
+    }
+}
@@ -0,0 +1,56 @@
+// debug_view.rs
+
+use nalgebra_glm::Vec3;
+use crate::camera::Camera;
+use crate::render_settings::RenderMode;
+use crate::scene::Scene;
+use crate::closest_intersect;
+
+// View-space depth shown as black in the depth view, closer surfaces are brighter
+const DEPTH_RANGE: f32 = 40.0;
+// Ray count shown as full red in the ray cost view
+const MAX_RAY_COST: u32 = 32;
+
+// Color of a pixel in one of the debug modes, read straight from the first
+// `Intersect` of the ray. Rays that miss everything are black. The ray cost
+// view needs the full shading instead, see `cost_color`.
+pub fn debug_color(mode: RenderMode, ray_origin: &Vec3, ray_direction: &Vec3, scene: &Scene, camera: &Camera) -> Vec3 {
+    let intersect = closest_intersect(ray_origin, ray_direction, scene);
+    if !intersect.is_intersecting {
+        return Vec3::zeros();
+    }
+
+    match mode {
+        RenderMode::Normal => intersect.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5),
+        // Depth, not distance, so flat walls facing the camera have a single shade
+        RenderMode::Depth => Vec3::repeat(1.0 - (camera.view_depth(&intersect.point) / DEPTH_RANGE).clamp(0.0, 1.0)),
+        RenderMode::Uv => Vec3::new(intersect.u, intersect.v, 0.0),
+        RenderMode::Albedo => intersect.material.get_diffuse_color(intersect.u, intersect.v).to_vec3(),
+        RenderMode::MaterialId => id_color(intersect.material.id()),
+        RenderMode::Shaded | RenderMode::RayCost => unreachable!(),
+    }
+}
+
+// Color of the ray cost view for the rays traced while shading a sample: camera,
+// reflection and refraction rays plus every shadow, occlusion, environment and fog ray
+pub fn cost_color(rays: u32) -> Vec3 {
+    heat_color(rays as f32 / MAX_RAY_COST as f32)
+}
+
+// Bright, well separated color for an ID
+pub fn id_color(id: u64) -> Vec3 {
+    // Scrambled first, consecutive IDs would only differ in the red channel otherwise
+    let id = id.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 40;
+    let channel = |shift: u32| 0.25 + 0.75 * ((id >> shift) & 0xFF) as f32 / 255.0;
+    Vec3::new(channel(0), channel(8), channel(16))
+}
+
+// Blue for 0, through green and yellow, to red for 1
+fn heat_color(t: f32) -> Vec3 {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    match t {
+        t if t < 1.0 => Vec3::new(0.0, t, 1.0 - t),
+        t if t < 2.0 => Vec3::new(t - 1.0, 1.0, 0.0),
+        t => Vec3::new(1.0, 3.0 - t, 0.0),
+    }
+}
@@ -1,14 +1,28 @@
 // diorama.rs
 
-use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use nalgebra_glm::{Mat4, Vec3};
+use crate::color::Color;
+use crate::cone::Cone;
+use crate::csg::Csg;
 use crate::cube::Cube;
+use crate::cylinder::Cylinder;
+use crate::instance::Instance;
+use crate::light::Light;
 use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::obj::load_obj;
+use crate::plane::{Disc, Plane};
+use crate::ray_intersect::RayIntersect;
+use crate::scene::{Prop, Scene};
+use crate::torus::Torus;
+use crate::world::World;
 
-pub fn generate_diorama() -> Vec<Cube> {
-    let mut objects = Vec::new();
-    let cube_size = 1.0; // Uniform size for all cubes
+pub fn generate_diorama() -> Scene {
+    let mut world = World::new();
 
-    // Material maps for layers
+    // Material maps for layers, indices into `world::palette`
 
     let layer_0 = [
         [2, 2, 2, 2, 2, 2, 2, 2], // Layer 0 map
@@ -114,70 +128,30 @@ pub fn generate_diorama() -> Vec<Cube> {
         [11, 0, 0, 0, 0, 0, 0, 0],
     ];
 
-    // Material key based on indices
-    let materials = vec![
-        Material::dirt(),           // 1
-        Material::obsidian(),       // 2
-        Material::crying_osidian(), // 3
-        Material::glass(),          // 4
-        Material::diamond_ore(),    // 5
-        Material::beacon(),         // 6
-        Material::crafting_table(), // 7
-        Material::tnt(),            // 8
-        Material::cobblestone(),    // 9
-        Material::bookshelf(),      // 10
-        Material::redstone_lamp(),  // 11
-    ];
-
-    // Function to process a single layer
-    let mut process_layer = |layer: &[[u8; 8]], y_layer: f32| {
-        for (z, row) in layer.iter().enumerate() {
-            for (x, &material_index) in row.iter().enumerate() {
-                if material_index > 0 {
-                    // Calculate cube coordinates
-                    let x_pos = x as f32 * cube_size;
-                    let z_pos = z as f32 * cube_size;
-
-                    // Convert `material_index` from u8 to usize
-                    let material = materials[(material_index - 1) as usize].clone();
-
-                    // Create a cube with the corresponding material
-                    let cube = Cube::new(
-                        Vec3::new(x_pos, y_layer, z_pos),                      // Minimum corner
-                        Vec3::new(x_pos + cube_size, y_layer + cube_size, z_pos + cube_size), // Maximum corner
-                        material,
-                    );
-
-                    objects.push(cube);
-                }
-            }
-        }
-    };
-
     // Process each layer with its respective height
-    process_layer(&layer_0, 0.0); // Layer 0 at y = 0
-    process_layer(&layer_1, 1.0); // Layer 1 at y = 1
-    process_layer(&layer_2, 2.0); // Layer 2 at y = 2
-    process_layer(&layer_3, 3.0); // Layer 3 at y = 3
-    process_layer(&layer_4, 4.0); // Layer 4 at y = 4
-    process_layer(&layer_5, 5.0); // Layer 5 at y = 5
-    process_layer(&layer_6, 6.0); // Layer 6 at y = 6
-    process_layer(&layer_7, 7.0); // Layer 7 at y = 7
-    process_layer(&layer_8, 8.0); // Layer 8 at y = 8
-    process_layer(&layer_9, 9.0); // Layer 9 at y = 9
-    process_layer(&layer_10, 10.0); // Layer 10 at y = 10
-
-    objects
+    world.add_layer(0, &layer_0, &[]); // Layer 0 at y = 0
+    world.add_layer(1, &layer_1, &[]); // Layer 1 at y = 1
+    world.add_layer(2, &layer_2, &[]); // Layer 2 at y = 2
+    world.add_layer(3, &layer_3, &[]); // Layer 3 at y = 3
+    world.add_layer(4, &layer_4, &[]); // Layer 4 at y = 4
+    world.add_layer(5, &layer_5, &[]); // Layer 5 at y = 5
+    world.add_layer(6, &layer_6, &[]); // Layer 6 at y = 6
+    world.add_layer(7, &layer_7, &[]); // Layer 7 at y = 7
+    world.add_layer(8, &layer_8, &[]); // Layer 8 at y = 8
+    world.add_layer(9, &layer_9, &[]); // Layer 9 at y = 9
+    world.add_layer(10, &layer_10, &[]); // Layer 10 at y = 10
+
+    Scene::new(world, Vec::new())
 }
 
 
 // Create a second diorama, more simple than the first one
-pub fn generate_diorama2() -> Vec<Cube> {
+pub fn generate_diorama2() -> Scene {
 
-    let mut objects = Vec::new();
-    let cube_size = 1.0; // Uniform size for all cubes
+    let mut world = World::new();
+    let mut props: Vec<Prop> = Vec::new();
 
-    // Material maps for layers
+    // Material maps for layers, indices into `world::palette`
 
     let layer_0 = [
         [0, 0, 0, 0, 0, 0, 0, 0], // Layer 0 map
@@ -193,14 +167,26 @@ pub fn generate_diorama2() -> Vec<Cube> {
     let layer_1 = [
         [0, 0, 0, 0, 0, 0, 0, 0],
         [0, 4, 0, 0, 0, 0, 4, 0],
-        [0, 0, 8, 0, 0, 8, 0, 0],
-        [0, 0, 0, 6, 6, 0, 0, 0],
-        [0, 0, 0, 6, 6, 0, 0, 0],
-        [0, 0, 8, 0, 0, 8, 0, 0],
+        [0, 0, 8, 2, 2, 8, 0, 0],
+        [0, 0, 9, 6, 6, 9, 0, 0],
+        [0, 0, 9, 6, 6, 9, 0, 0],
+        [0, 0, 8, 2, 2, 8, 0, 0],
         [0, 4, 0, 0, 0, 0, 4, 0],
         [0, 0, 0, 0, 0, 0, 0, 0],
     ];
 
+    // Glass panes over the lamps, slabs and stairs leading up to the beacon
+    let shapes_1 = [
+        "........",
+        ".P....P.",
+        "...__...",
+        "..E..W..",
+        "..E..W..",
+        "...__...",
+        ".P....P.",
+        "........",
+    ];
+
     let layer_2 = [
         [0, 0, 0, 0, 0, 0, 0, 0],
         [0, 0, 0, 0, 0, 0, 0, 0],
@@ -213,51 +199,104 @@ pub fn generate_diorama2() -> Vec<Cube> {
     ];
 
 
-    // Material key based on indices
-    let materials = vec![
-        Material::dirt(),           // 1
-        Material::obsidian(),       // 2
-        Material::crying_osidian(), // 3
-        Material::glass(),          // 4
-        Material::diamond_ore(),    // 5
-        Material::beacon(),         // 6
-        Material::crafting_table(), // 7
-        Material::tnt(),            // 8
-        Material::cobblestone(),    // 9
-        Material::bookshelf(),      // 10
-        Material::redstone_lamp(),  // 11
-    ];
+    // Process each layer with its respective height
+    world.add_layer(0, &layer_0, &[]); // Layer 0 at y = 0
+    world.add_layer(1, &layer_1, &shapes_1); // Layer 1 at y = 1
+    world.add_layer(2, &layer_2, &[]); // Layer 2 at y = 2
+
+    // The diorama still renders without the signs if their model can't be loaded
+    match signs() {
+        Ok(signs) => props.extend(signs.into_iter().map(|sign| vec![sign])),
+        Err(message) => eprintln!("{}", message),
+    }
+
+    // Beacon beam with a floating gold ring around it
+    let beam = Cylinder::new(Vec3::new(4.0, 3.0, 4.0), 0.25, 6.0, Material::beacon_beam());
+    let ring: Arc<dyn RayIntersect> = Arc::new(Torus::new(Vec3::zeros(), 0.9, 0.08, Material::gold()));
+    let ring = Instance::new(ring)
+        .rotate(PI / 10.0, Vec3::new(1.0, 0.0, 0.0))
+        .translate(Vec3::new(4.0, 5.0, 4.0));
+
+    props.push(vec![Box::new(beam)]);
+    props.push(vec![Box::new(ring)]);
+
+    // Torches standing on the glass panes
+    for (x, z) in [(1.5, 1.5), (6.5, 1.5), (1.5, 6.5), (6.5, 6.5)] {
+        let stick = Cylinder::new(Vec3::new(x, 2.0, z), 0.06, 0.5, Material::oak());
+        let flame = Cone::new(Vec3::new(x, 2.5, z), 0.08, 0.2, Material::torch_flame());
+
+        props.push(vec![Box::new(stick), Box::new(flame)]);
+    }
+
+    // Cobblestone wall with a round glass window, topped by a hollow gold frame
+    match window_wall() {
+        Ok(parts) => props.push(parts),
+        Err(message) => eprintln!("{}", message),
+    }
+
+    // Cobblestone platform on an endless grass field
+    let platform = Disc::new(Vec3::new(4.0, 0.01, 4.0), Vec3::new(0.0, 1.0, 0.0), 6.0, Material::cobblestone())
+        .with_uv_scale(2.0);
+    let grass = Material::new(Color::new(96, 160, 64), 5.0, [0.8, 0.1, 0.0, 0.0], 0.0);
+    let field = Plane::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), grass)
+        .with_uv_scale(2.0)
+        .with_checker(Color::new(80, 140, 52));
+
+    props.push(vec![Box::new(platform)]);
+    props.push(vec![Box::new(field)]);
+
+    // Fog around the beam and the flames glows with their light
+    let mut scene = Scene::new(world, props);
+    scene.glows.push(Light::glow(Vec3::new(4.0, 6.0, 4.0), Material::beacon_beam().emission));
+    for (x, z) in [(1.5, 1.5), (6.5, 1.5), (1.5, 6.5), (6.5, 6.5)] {
+        scene.glows.push(Light::glow(Vec3::new(x, 2.6, z), Material::torch_flame().emission));
+    }
+    scene
 
-    // Function to process a single layer
-    let mut process_layer = |layer: &[[u8; 8]], y_layer: f32| {
-        for (z, row) in layer.iter().enumerate() {
-            for (x, &material_index) in row.iter().enumerate() {
-                if material_index > 0 {
-                    // Calculate cube coordinates
-                    let x_pos = x as f32 * cube_size;
-                    let z_pos = z as f32 * cube_size;
-
-                    // Convert `material_index` from u8 to usize
-                    let material = materials[(material_index - 1) as usize].clone();
-
-                    // Create a cube with the corresponding material
-                    let cube = Cube::new(
-                        Vec3::new(x_pos, y_layer, z_pos),                      // Minimum corner
-                        Vec3::new(x_pos + cube_size, y_layer + cube_size, z_pos + cube_size), // Maximum corner
-                        material,
-                    );
-
-                    objects.push(cube);
-                }
-            }
-        }
-    };
+}
 
-    // Process each layer with its respective height
-    process_layer(&layer_0, 0.0); // Layer 0 at y = 0
-    process_layer(&layer_1, 1.0); // Layer 1 at y = 1
-    process_layer(&layer_2, 2.0); // Layer 2 at y = 2
+// Signs on the obsidian next to the lamps, sharing a single sign mesh
+fn signs() -> Result<Vec<Box<dyn RayIntersect>>, String> {
+    let sign: Arc<dyn RayIntersect> = Arc::new(Mesh::new(load_obj("assets/sign.obj", &Mat4::identity())?));
 
-    objects
+    let sign_a = Instance::new(sign.clone())
+        .translate(Vec3::new(2.5, 1.0, 1.5));
+    let sign_b = Instance::new(sign)
+        .scale(Vec3::new(0.8, 0.8, 0.8))?
+        .rotate(PI / 12.0, Vec3::new(0.0, 0.0, 1.0)) // Slightly knocked over
+        .rotate(PI, Vec3::new(0.0, 1.0, 0.0))
+        .translate(Vec3::new(5.5, 1.0, 6.5));
 
+    Ok(vec![Box::new(sign_a), Box::new(sign_b)])
+}
+
+// Cobblestone wall with a round glass window, topped by a hollow gold frame
+fn window_wall() -> Result<Vec<Box<dyn RayIntersect>>, String> {
+    let window = |material: Material| {
+        let cutter: Arc<dyn RayIntersect> = Arc::new(Cylinder::new(Vec3::zeros(), 0.6, 1.0, material));
+        Box::new(Instance::new(cutter)
+            .rotate(PI / 2.0, Vec3::new(1.0, 0.0, 0.0))
+            .translate(Vec3::new(4.0, 1.0, -1.35)))
+    };
+    let wall = Csg::difference(
+        Box::new(Cube::new(Vec3::new(3.0, 0.0, -1.0), Vec3::new(5.0, 2.0, -0.7), Material::cobblestone())),
+        window(Material::cobblestone()),
+    )?;
+    let pane = Csg::intersection(
+        window(Material::glass()),
+        Box::new(Cube::new(Vec3::new(3.0, 0.0, -0.87), Vec3::new(5.0, 2.0, -0.83), Material::glass())),
+    )?;
+    let bar = |min: Vec3, max: Vec3| -> Box<dyn RayIntersect> { Box::new(Cube::new(min, max, Material::gold())) };
+    let frame = Csg::difference(
+        bar(Vec3::new(3.5, 2.0, -1.35), Vec3::new(4.5, 3.0, -0.35)),
+        Box::new(Csg::union(
+            Box::new(Csg::union(
+                bar(Vec3::new(3.4, 2.1, -1.25), Vec3::new(4.6, 2.9, -0.45)),
+                bar(Vec3::new(3.6, 1.9, -1.25), Vec3::new(4.4, 3.1, -0.45)),
+            )?),
+            bar(Vec3::new(3.6, 2.1, -1.45), Vec3::new(4.4, 2.9, -0.25)),
+        )?),
+    )?;
+
+    Ok(vec![Box::new(wall), Box::new(pane), Box::new(frame)])
 }
@@ -0,0 +1,92 @@
+// dynamic_resolution.rs
+
+// Smallest fraction of the render scale used while the camera moves
+const MIN_FACTOR: f32 = 0.25;
+
+// Lowers the resolution of the viewer while the camera moves so every frame
+// renders within the time of `target_fps`, and goes back to full resolution
+// as soon as the view is still
+pub struct DynamicResolution {
+    pub enabled: bool,
+    pub target_fps: f32,
+    // Fraction of the render scale used for moving frames, kept between movements
+    moving_factor: f32,
+}
+
+impl DynamicResolution {
+    pub fn new(target_fps: f32) -> Self {
+        DynamicResolution {
+            enabled: target_fps > 0.0,
+            target_fps: if target_fps > 0.0 { target_fps } else { 30.0 },
+            moving_factor: 1.0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    // Fraction of the render scale to use for the next frame
+    pub fn factor(&self, moving: bool) -> f32 {
+        if self.enabled && moving {
+            self.moving_factor
+        } else {
+            1.0
+        }
+    }
+
+    // Updates the moving factor from the seconds the last moving frame took to render.
+    // Render time grows with the pixel count, the square of the factor.
+    pub fn update(&mut self, render_time: f32, moving: bool) {
+        if !self.enabled || !moving || render_time <= 0.0 {
+            return;
+        }
+
+        let budget = 1.0 / self.target_fps;
+        let ideal = self.moving_factor * (budget / render_time).sqrt();
+        // Halfway to the ideal factor, so a single slow frame does not make it jump
+        self.moving_factor = ((self.moving_factor + ideal) * 0.5).clamp(MIN_FACTOR, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factor_drops_over_budget_and_recovers_under_it() {
+        let mut resolution = DynamicResolution::new(30.0);
+        assert_eq!(resolution.factor(true), 1.0);
+
+        // Four times the budget, so the ideal factor is a half
+        resolution.update(4.0 / 30.0, true);
+        let slow = resolution.factor(true);
+        assert!(slow < 1.0, "{}", slow);
+        // A still view always renders at full resolution
+        assert_eq!(resolution.factor(false), 1.0);
+
+        resolution.update(0.1 / 30.0, true);
+        assert!(resolution.factor(true) > slow);
+    }
+
+    #[test]
+    fn factor_stays_clamped() {
+        let mut resolution = DynamicResolution::new(30.0);
+        for _ in 0..50 {
+            resolution.update(10.0, true);
+        }
+        assert_eq!(resolution.factor(true), MIN_FACTOR);
+
+        for _ in 0..50 {
+            resolution.update(1e-6, true);
+        }
+        assert_eq!(resolution.factor(true), 1.0);
+    }
+
+    #[test]
+    fn disabled_keeps_full_resolution() {
+        let mut resolution = DynamicResolution::new(0.0);
+        resolution.update(10.0, true);
+        assert_eq!(resolution.factor(true), 1.0);
+    }
+}
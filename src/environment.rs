@@ -0,0 +1,305 @@
+// environment.rs
+
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use crate::camera::{equirectangular_direction, equirectangular_uv, CubeFace};
+use crate::color::Color;
+use crate::sky::Sky;
+
+// el skybox debe tener un color azul oscuro
+pub const SKYBOX_COLOR: Color = Color::new(40, 24, 128);
+
+// Resolution of the grid directions are importance sampled from
+const DISTRIBUTION_WIDTH: usize = 64;
+const DISTRIBUTION_HEIGHT: usize = 32;
+// Hours the sun of a procedural sky moves before the grid is rebuilt. Sampling
+// from a slightly outdated grid is noisier but still unbiased.
+const DISTRIBUTION_TIME_STEP: f32 = 0.25;
+
+// Linear RGB image, sampled with bilinear filtering
+struct EnvironmentImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl EnvironmentImage {
+    // HDR and EXR files keep their linear values, 8-bit images go from 0 to 1
+    fn load(file_path: &str) -> Result<Self, String> {
+        let image = image::open(file_path)
+            .map_err(|e| format!("Failed to open {}: {}", file_path, e))?
+            .into_rgb32f();
+
+        Ok(EnvironmentImage {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels: image.pixels().map(|pixel| Vec3::new(pixel[0], pixel[1], pixel[2])).collect(),
+        })
+    }
+
+    // `u` and `v` in [0, 1]. Columns wrap around with `wrap`, rows are always clamped.
+    fn sample(&self, u: f32, v: f32, wrap: bool) -> Vec3 {
+        let x = u * self.width as f32 - 0.5;
+        let y = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let column = |x: f32| {
+            if wrap {
+                (x as i32).rem_euclid(self.width as i32) as usize
+            } else {
+                x.clamp(0.0, (self.width - 1) as f32) as usize
+            }
+        };
+        let (left, right) = (column(x0), column(x0 + 1.0));
+        let (top, bottom) = (y0 as usize, (y0 as usize + 1).min(self.height - 1));
+
+        let pixel = |x: usize, y: usize| self.pixels[y * self.width + x];
+        let upper = pixel(left, top) * (1.0 - tx) + pixel(right, top) * tx;
+        let lower = pixel(left, bottom) * (1.0 - tx) + pixel(right, bottom) * tx;
+        upper * (1.0 - ty) + lower * ty
+    }
+}
+
+// What rays that miss every object see
+enum Background {
+    Color(Vec3),
+    // Longitude across and latitude down, like the equirectangular projection
+    Equirectangular(EnvironmentImage),
+    // Faces in the order of `CubeFace::ALL`, oriented like the cubemap projection
+    Cubemap(Vec<EnvironmentImage>),
+    // Procedural daylight sky with the sun
+    Sky(Sky),
+}
+
+impl Background {
+    fn sample(&self, direction: &Vec3, sun_disk: bool) -> Vec3 {
+        match self {
+            Background::Color(color) => *color,
+            Background::Equirectangular(image) => {
+                let (u, v) = equirectangular_uv(direction);
+                image.sample(u, v, true)
+            }
+            Background::Cubemap(faces) => {
+                let (face, screen_x, screen_y) = CubeFace::from_direction(direction);
+                let index = CubeFace::ALL.iter().position(|f| *f == face).unwrap();
+                faces[index].sample((screen_x + 1.0) * 0.5, (1.0 - screen_y) * 0.5, false)
+            }
+            Background::Sky(sky) => sky.sample(direction, sun_disk),
+        }
+    }
+}
+
+// Brightness of every cell of a latitude-longitude grid over the environment,
+// to pick directions for image-based lighting where most of the light comes from
+struct Distribution {
+    // Running sum of the cell weights, ending at 1
+    cdf: Vec<f32>,
+}
+
+impl Distribution {
+    fn new(background: &Background, sun_disk: bool) -> Self {
+        let mut cdf = Vec::with_capacity(DISTRIBUTION_WIDTH * DISTRIBUTION_HEIGHT);
+        let mut total = 0.0;
+
+        for y in 0..DISTRIBUTION_HEIGHT {
+            // Cells near the poles cover less of the sphere
+            let v = (y as f32 + 0.5) / DISTRIBUTION_HEIGHT as f32;
+            let area = (v * PI).sin();
+
+            for x in 0..DISTRIBUTION_WIDTH {
+                let u = (x as f32 + 0.5) / DISTRIBUTION_WIDTH as f32;
+                total += luminance(&background.sample(&equirectangular_direction(u, v), sun_disk)) * area;
+                cdf.push(total);
+            }
+        }
+
+        // A black environment is sampled uniformly
+        if total <= 0.0 {
+            let count = cdf.len() as f32;
+            cdf.iter_mut().enumerate().for_each(|(i, value)| *value = (i + 1) as f32 / count);
+        } else {
+            cdf.iter_mut().for_each(|value| *value /= total);
+        }
+
+        Distribution { cdf }
+    }
+
+    // Direction in environment space with its probability density per steradian
+    fn sample(&self, rng: &mut impl Rng) -> (Vec3, f32) {
+        let target: f32 = rng.gen();
+        let cell = self.cdf.partition_point(|value| *value < target).min(self.cdf.len() - 1);
+        let probability = self.cdf[cell] - if cell > 0 { self.cdf[cell - 1] } else { 0.0 };
+
+        let u = ((cell % DISTRIBUTION_WIDTH) as f32 + rng.gen::<f32>()) / DISTRIBUTION_WIDTH as f32;
+        let v = ((cell / DISTRIBUTION_WIDTH) as f32 + rng.gen::<f32>()) / DISTRIBUTION_HEIGHT as f32;
+
+        // The cell spans 2π / width by π / height of longitude and latitude
+        let cell_solid_angle =
+            2.0 * PI * PI * (v * PI).sin() / (DISTRIBUTION_WIDTH * DISTRIBUTION_HEIGHT) as f32;
+        (equirectangular_direction(u, v), probability / cell_solid_angle.max(1e-6))
+    }
+}
+
+fn luminance(color: &Vec3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+// Light coming from infinitely far away in every direction. Rays that miss
+// the scene, including reflected and refracted ones, return it.
+pub struct Environment {
+    background: Background,
+    // Radians around the y axis
+    pub rotation: f32,
+    pub intensity: f32,
+    // Directions sampled per diffuse hit for image-based lighting, 0 disables it
+    pub ibl_samples: u32,
+    distribution: Option<Distribution>,
+    // Time of the procedural sky `distribution` was built for
+    distribution_time: f32,
+    // The sun of a procedural sky is lit by a light of its own, so image-based
+    // lighting leaves the sun disk out instead of counting it twice
+    sun_light: bool,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::color(SKYBOX_COLOR.to_vec3())
+    }
+}
+
+impl Environment {
+    pub fn color(color: Vec3) -> Self {
+        Environment {
+            background: Background::Color(color),
+            rotation: 0.0,
+            intensity: 1.0,
+            ibl_samples: 0,
+            distribution: None,
+            distribution_time: 0.0,
+            sun_light: false,
+        }
+    }
+
+    pub fn sky(sky: Sky) -> Self {
+        Environment {
+            background: Background::Sky(sky),
+            ..Environment::default()
+        }
+    }
+
+    // An equirectangular image, or a directory with the six faces of a cubemap
+    // named px, nx, py, ny, pz and nz, as written by `--projection cubemap`
+    pub fn load(path: &str) -> Result<Self, String> {
+        let background = if std::path::Path::new(path).is_dir() {
+            let faces = CubeFace::ALL
+                .iter()
+                .map(|face| find_face(path, face.name()).and_then(|file_path| EnvironmentImage::load(&file_path)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Background::Cubemap(faces)
+        } else {
+            Background::Equirectangular(EnvironmentImage::load(path)?)
+        };
+
+        Ok(Environment {
+            background,
+            ..Environment::default()
+        })
+    }
+
+    pub fn with_rotation(mut self, degrees: f32) -> Self {
+        self.rotation = degrees.to_radians();
+        self
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_ibl(mut self, samples: u32) -> Self {
+        self.ibl_samples = samples;
+        if samples > 0 {
+            self.rebuild_distribution();
+        }
+        self
+    }
+
+    fn rebuild_distribution(&mut self) {
+        self.distribution = Some(Distribution::new(&self.background, !self.sun_light));
+        self.distribution_time = self.get_sky().map_or(0.0, |sky| sky.time);
+    }
+
+    pub fn rotate(&mut self, angle: f32) {
+        self.rotation = (self.rotation + angle).rem_euclid(2.0 * PI);
+    }
+
+    pub fn change_intensity(&mut self, factor: f32) {
+        self.intensity = (self.intensity * factor).clamp(0.01, 100.0);
+    }
+
+    pub fn get_sky(&self) -> Option<&Sky> {
+        match &self.background {
+            Background::Sky(sky) => Some(sky),
+            _ => None,
+        }
+    }
+
+    // Whether a separate light follows the sun of a procedural sky
+    pub fn set_sun_light(&mut self, active: bool) {
+        if self.sun_light != active {
+            self.sun_light = active;
+            if self.distribution.is_some() && self.get_sky().is_some() {
+                self.rebuild_distribution();
+            }
+        }
+    }
+
+    // Moves the sun of a procedural sky. Does nothing for other environments.
+    pub fn advance_time(&mut self, hours: f32) {
+        let Background::Sky(sky) = &mut self.background else {
+            return;
+        };
+        sky.advance(hours);
+
+        // The brightest directions moved with the sun, but rebuilding the grid every
+        // frame while the time changes would be too slow
+        let moved = (sky.time - self.distribution_time).rem_euclid(24.0);
+        if self.distribution.is_some() && moved.min(24.0 - moved) >= DISTRIBUTION_TIME_STEP {
+            self.rebuild_distribution();
+        }
+    }
+
+    // Direction towards the sun of a procedural sky, with the color and strength
+    // of its light, which fades to 0 at night
+    pub fn sun(&self) -> Option<(Vec3, Vec3, f32)> {
+        let sky = self.get_sky()?;
+        let direction = nalgebra_glm::rotate_y_vec3(&sky.sun_direction(), self.rotation);
+        Some((direction, sky.sun_color(), sky.daylight()))
+    }
+
+    // Radiance arriving from `direction`
+    pub fn sample(&self, direction: &Vec3) -> Vec3 {
+        let local = nalgebra_glm::rotate_y_vec3(direction, -self.rotation);
+        self.background.sample(&local, true) * self.intensity
+    }
+
+    // Direction towards the environment chosen in proportion to its brightness, with the
+    // radiance from it and the probability density per steradian. None without IBL.
+    pub fn sample_light(&self, rng: &mut impl Rng) -> Option<(Vec3, Vec3, f32)> {
+        let distribution = self.distribution.as_ref()?;
+        let (local, pdf) = distribution.sample(rng);
+        let direction = nalgebra_glm::rotate_y_vec3(&local, self.rotation);
+        Some((direction, self.background.sample(&local, !self.sun_light) * self.intensity, pdf))
+    }
+}
+
+// Face image with any of the extensions `image` can read
+fn find_face(directory: &str, name: &str) -> Result<String, String> {
+    ["png", "hdr", "exr", "jpg", "jpeg"]
+        .iter()
+        .map(|extension| format!("{}/{}.{}", directory, name, extension))
+        .find(|file_path| std::path::Path::new(file_path).is_file())
+        .ok_or(format!("Missing cubemap face {} in {}", name, directory))
+}
@@ -0,0 +1,144 @@
+// export.rs
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::time::{SystemTime, UNIX_EPOCH};
+use exr::prelude::{f16, AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, SmallVec, Vec2, WritableImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::hdr::HdrEncoder;
+use image::{Delay, Frame, Rgb, RgbImage, RgbaImage};
+use nalgebra_glm::Vec3;
+use crate::aov::{Pass, Passes};
+use crate::framebuffer::Framebuffer;
+
+pub fn to_rgb_image(framebuffer: &Framebuffer) -> RgbImage {
+    RgbImage::from_fn(framebuffer.width as u32, framebuffer.height as u32, |x, y| {
+        let hex = framebuffer.buffer[y as usize * framebuffer.width + x as usize];
+        image::Rgb([(hex >> 16) as u8, (hex >> 8) as u8, hex as u8])
+    })
+}
+
+pub fn save_png(framebuffer: &Framebuffer, file_path: &str) -> Result<(), String> {
+    to_rgb_image(framebuffer)
+        .save(file_path)
+        .map_err(|e| format!("Failed to save {}: {}", file_path, e))
+}
+
+// Current UTC date and time as `2024-05-31_18-04-59`, for file names
+pub fn timestamp() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year, month, day, time / 3600, time % 3600 / 60, time % 60
+    )
+}
+
+// Animated GIF written frame by frame, so long animations are not kept in memory
+pub struct GifWriter {
+    encoder: GifEncoder<File>,
+    delay: Delay,
+}
+
+impl GifWriter {
+    pub fn create(file_path: &str, fps: u32) -> Result<Self, String> {
+        let file = File::create(file_path).map_err(|e| format!("Failed to create {}: {}", file_path, e))?;
+        let mut encoder = GifEncoder::new_with_speed(file, 10);
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+
+        Ok(GifWriter {
+            encoder,
+            delay: Delay::from_numer_denom_ms(1000, fps.max(1)),
+        })
+    }
+
+    pub fn add_frame(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        let rgba: RgbaImage = image::DynamicImage::ImageRgb8(to_rgb_image(framebuffer)).to_rgba8();
+        self.encoder
+            .encode_frame(Frame::from_parts(rgba, 0, 0, self.delay))
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Every pass as an 8-bit PNG named after it inside `directory`
+pub fn save_passes_png(passes: &Passes, directory: &str) -> Result<(), String> {
+    fs::create_dir_all(directory).map_err(|e| format!("Failed to create {}: {}", directory, e))?;
+
+    for pass in Pass::ALL {
+        let values: Vec<Vec3> = passes.display_values(pass);
+        let image = RgbImage::from_fn(passes.width as u32, passes.height as u32, |x, y| {
+            let value = values[y as usize * passes.width + x as usize] * 255.0;
+            image::Rgb([value.x as u8, value.y as u8, value.z as u8])
+        });
+
+        let file_path = format!("{}/{}.png", directory, pass.name());
+        image.save(&file_path).map_err(|e| format!("Failed to save {}: {}", file_path, e))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    // 16-bit half floats, half the size and enough for color
+    Half,
+    Float,
+}
+
+// Linear render as a single OpenEXR layer: the beauty pass in R, G and B and depth
+// in Z. With `all_passes` every other pass is added as channels named like
+// `diffuse.R`, which compositing tools show as separate layers.
+pub fn save_exr(passes: &Passes, file_path: &str, all_passes: bool, precision: Precision) -> Result<(), String> {
+    let mut channels = Vec::new();
+
+    for pass in Pass::ALL {
+        let prefix = match pass {
+            Pass::Beauty | Pass::Depth => String::new(),
+            _ if all_passes => format!("{}.", pass.name()),
+            _ => continue,
+        };
+
+        for (index, name) in pass.channels().iter().enumerate() {
+            let values = passes.values(pass).map(|value| value[index]);
+            // IDs stay in full floats so they are exact
+            let samples = match precision {
+                Precision::Half if pass != Pass::ObjectId => FlatSamples::F16(values.map(f16::from_f32).collect()),
+                _ => FlatSamples::F32(values.collect()),
+            };
+            channels.push(AnyChannel::new(format!("{}{}", prefix, name).as_str(), samples));
+        }
+    }
+
+    let layer = Layer::new(
+        Vec2(passes.width, passes.height),
+        LayerAttributes::default(),
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(SmallVec::from_vec(channels)),
+    );
+    Image::from_layer(layer)
+        .write()
+        .to_file(file_path)
+        .map_err(|e| format!("Failed to save {}: {}", file_path, e))
+}
+
+// Beauty pass as a Radiance .hdr file, which only holds RGB
+pub fn save_hdr(passes: &Passes, file_path: &str) -> Result<(), String> {
+    let pixels: Vec<Rgb<f32>> = passes.values(Pass::Beauty).map(|color| Rgb([color.x, color.y, color.z])).collect();
+
+    let file = File::create(file_path).map_err(|e| format!("Failed to create {}: {}", file_path, e))?;
+    HdrEncoder::new(BufWriter::new(file))
+        .encode(&pixels, passes.width, passes.height)
+        .map_err(|e| format!("Failed to save {}: {}", file_path, e))
+}
@@ -0,0 +1,214 @@
+// fog.rs
+
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use crate::light::Light;
+use crate::ray_intersect::RayCount;
+use crate::scene::Scene;
+
+// Rays that miss everything still cross this much fog before reaching the sky
+const MAX_FOG_DISTANCE: f32 = 60.0;
+// Glows sit inside the block or prop they stand for, occluders this close to
+// one are taken as its own surface and don't shadow it
+const GLOW_RADIUS: f32 = 0.9;
+
+// Participating medium filling the scene. Light is absorbed along every ray and
+// lights scattered towards the camera, which makes shafts behind occluders visible.
+#[derive(Debug, Clone, Copy)]
+pub struct Fog {
+    pub enabled: bool,
+    // Extinction per world unit at `base_height`
+    pub density: f32,
+    // Tint of the light scattered by the fog
+    pub color: Vec3,
+    // Henyey-Greenstein asymmetry, from -1 (back towards the light) to 1 (forward)
+    pub anisotropy: f32,
+    // 0 is homogeneous fog, larger values thin it out faster above `base_height`,
+    // below it the fog keeps `density`
+    pub height_falloff: f32,
+    pub base_height: f32,
+    // Points sampled along every ray, each with a shadow ray towards the light
+    pub steps: u32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            enabled: false,
+            density: 0.05,
+            color: Vec3::new(0.8, 0.85, 0.9),
+            anisotropy: 0.3,
+            height_falloff: 0.0,
+            base_height: 0.0,
+            steps: 16,
+        }
+    }
+}
+
+impl Fog {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    pub fn density_at(&self, point: &Vec3) -> f32 {
+        if self.height_falloff > 0.0 {
+            self.density * (-self.height_falloff * (point.y - self.base_height)).exp().min(1.0)
+        } else {
+            self.density
+        }
+    }
+
+    // Light scattered from the light direction into the view direction, with `cosine`
+    // between the direction the light travels and the scattered one. Relative to
+    // isotropic scattering, like diffuse lighting here leaves out the 1 / π.
+    fn phase(&self, cosine: f32) -> f32 {
+        let g = self.anisotropy.clamp(-0.99, 0.99);
+        let denominator = 1.0 + g * g - 2.0 * g * cosine;
+        (1.0 - g * g) / (denominator * denominator.sqrt())
+    }
+}
+
+// Color seen through the fog along a ray that reaches `color` after `distance`
+// (infinite for rays that miss): attenuated by the fog in between plus the light
+// the fog scatters towards the origin, ray marched with jittered steps. Every
+// step is lit by `light` and by one of the glows of the scene picked at random.
+pub fn apply_fog(
+    color: Vec3,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    distance: f32,
+    scene: &Scene,
+    light: &Light,
+    rays: &RayCount,
+) -> Vec3 {
+    let fog = &scene.fog;
+    if !fog.enabled || fog.density <= 0.0 {
+        return color;
+    }
+
+    let length = distance.min(MAX_FOG_DISTANCE);
+    let steps = fog.steps.max(1);
+    let step = length / steps as f32;
+    let mut rng = rand::thread_rng();
+    let jitter: f32 = rng.gen();
+
+    let mut optical_depth = 0.0;
+    let mut scattered = Vec3::zeros();
+    for i in 0..steps {
+        let point = ray_origin + ray_direction * ((i as f32 + jitter) * step);
+        let density = fog.density_at(&point);
+        // Transmittance back to the origin, up to the sample point within this step
+        let transmittance = (-(optical_depth + density * step * jitter)).exp();
+        optical_depth += density * step;
+        if density <= 0.0 {
+            continue;
+        }
+
+        let mut incoming = in_scattering(&point, ray_direction, light, 0.0, scene, rays);
+        // Scaled by the number of glows, so on average each one adds its own light
+        if let Some((glow, count)) = scene.random_glow(&mut rng) {
+            incoming += in_scattering(&point, ray_direction, glow, GLOW_RADIUS, scene, rays) * count as f32;
+        }
+        scattered += incoming * (density * step * transmittance);
+    }
+
+    color * (-optical_depth).exp() + fog.color.component_mul(&scattered)
+}
+
+// Light from `light` scattered at `point` towards the origin of a ray along
+// `ray_direction`, ignoring occluders within `radius` of the light
+fn in_scattering(point: &Vec3, ray_direction: &Vec3, light: &Light, radius: f32, scene: &Scene, rays: &RayCount) -> Vec3 {
+    let (light_dir, light_distance, intensity) = light.illuminate(point);
+    if intensity <= 0.0 {
+        return Vec3::zeros();
+    }
+    rays.add(1);
+    let blocked = scene.objects().any(|object| {
+        let hit = object.ray_intersect(point, &light_dir);
+        hit.is_intersecting && hit.distance < light_distance - radius
+    });
+    if blocked {
+        return Vec3::zeros();
+    }
+
+    light.color.to_vec3() * (intensity * scene.fog.phase(ray_direction.dot(&light_dir)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::world::World;
+
+    // Color of the surface the test rays reach
+    const SURFACE: Vec3 = Vec3::new(1.0, 0.5, 0.25);
+
+    // Empty scene filled with fog of `density`, thinning out above y = 0 with `height_falloff`
+    fn foggy_scene(density: f32, height_falloff: f32) -> Scene {
+        let mut scene = Scene::new(World::new(), Vec::new());
+        scene.fog = Fog {
+            enabled: true,
+            density,
+            height_falloff,
+            // Isotropic and white, so the scattered light is easy to predict
+            anisotropy: 0.0,
+            color: Vec3::repeat(1.0),
+            ..Fog::default()
+        };
+        scene
+    }
+
+    fn sun(intensity: f32) -> Light {
+        Light::directional(Vec3::new(0.0, -1.0, 0.0), Color::new(255, 255, 255), intensity)
+    }
+
+    fn fogged(scene: &Scene, light: &Light, origin: Vec3, distance: f32) -> Vec3 {
+        apply_fog(SURFACE, &origin, &Vec3::new(1.0, 0.0, 0.0), distance, scene, light, &RayCount::default())
+    }
+
+    #[test]
+    fn density_thins_out_above_the_base_height() {
+        let fog = Fog { density: 0.2, height_falloff: 0.5, base_height: 1.0, ..Fog::default() };
+        let density = |y: f32| fog.density_at(&Vec3::new(3.0, y, -2.0));
+
+        assert_eq!(density(1.0), 0.2);
+        assert_eq!(density(-5.0), 0.2);
+        assert!((density(3.0) - 0.2 * (-1.0f32).exp()).abs() < 1e-6);
+
+        let homogeneous = Fog { density: 0.2, height_falloff: 0.0, ..Fog::default() };
+        assert_eq!(homogeneous.density_at(&Vec3::new(0.0, 50.0, 0.0)), 0.2);
+    }
+
+    #[test]
+    fn transmittance_follows_beer_lambert() {
+        let scene = foggy_scene(0.1, 0.0);
+        let dark = sun(0.0);
+
+        let seen = fogged(&scene, &dark, Vec3::zeros(), 10.0);
+        assert!((seen - SURFACE * (-1.0f32).exp()).magnitude() < 1e-5, "{:?}", seen);
+
+        // Rays that miss everything cross a limited amount of fog
+        let sky = fogged(&scene, &dark, Vec3::zeros(), f32::INFINITY);
+        assert!((sky - SURFACE * (-0.1 * MAX_FOG_DISTANCE).exp()).magnitude() < 1e-5, "{:?}", sky);
+
+        // Horizontal rays higher up cross thinner fog
+        let thinning = foggy_scene(0.1, 0.5);
+        let high = fogged(&thinning, &dark, Vec3::new(0.0, 2.0, 0.0), 10.0);
+        assert!((high - SURFACE * (-(-1.0f32).exp()).exp()).magnitude() < 1e-5, "{:?}", high);
+
+        let mut disabled = foggy_scene(0.1, 0.0);
+        disabled.fog.toggle();
+        assert_eq!(fogged(&disabled, &dark, Vec3::zeros(), 10.0), SURFACE);
+    }
+
+    #[test]
+    fn lit_fog_adds_the_light_it_does_not_let_through() {
+        let scene = foggy_scene(0.1, 0.0);
+        // Isotropic white fog lit by an unblocked light scatters (1 - transmittance) of it
+        let seen = fogged(&scene, &sun(2.0), Vec3::zeros(), 10.0);
+        let transmittance = (-1.0f32).exp();
+        let expected = SURFACE * transmittance + Vec3::repeat(2.0 * (1.0 - transmittance));
+
+        assert!((seen - expected).magnitude() < 0.05 * expected.magnitude(), "{:?} {:?}", seen, expected);
+    }
+}
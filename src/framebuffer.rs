@@ -1,5 +1,7 @@
 // framebuffer.rs
 
+use crate::font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
@@ -38,4 +40,105 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
-}
\ No newline at end of file
+
+    // Bresenham line between two points, clipped to the framebuffer
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            if x >= 0 && y >= 0 {
+                self.point(x as usize, y as usize);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Outline of a rectangle with its top left corner at (x, y)
+    pub fn rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (left, top) = (x as i32, y as i32);
+        let (right, bottom) = (left + width as i32 - 1, top + height as i32 - 1);
+
+        self.line(left, top, right, top);
+        self.line(left, bottom, right, bottom);
+        self.line(left, top, left, bottom);
+        self.line(right, top, right, bottom);
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.point(px, py);
+            }
+        }
+    }
+
+    // Draws text with the bitmap font, every font pixel `scale` pixels wide.
+    // Only the glyph pixels are drawn, so the background shows through.
+    pub fn text(&mut self, x: usize, y: usize, text: &str, scale: usize) {
+        for (index, character) in text.chars().enumerate() {
+            let glyph_x = x + index * (GLYPH_WIDTH + 1) * scale;
+
+            for (row, bits) in glyph(character).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill_rect(glyph_x + column * scale, y + row * scale, scale, scale);
+                    }
+                }
+            }
+        }
+    }
+
+    // Size in pixels of `text` drawn by `text()`, including the space after the last glyph
+    pub fn text_size(text: &str, scale: usize) -> (usize, usize) {
+        let characters = text.chars().count();
+        (characters * (GLYPH_WIDTH + 1) * scale, GLYPH_HEIGHT * scale)
+    }
+
+    // Copies the image into a framebuffer of another size. Every target pixel
+    // averages the source pixels it covers, or takes the nearest one when enlarging.
+    pub fn resample_into(&self, target: &mut Framebuffer) {
+        let (width, height) = (target.width, target.height);
+
+        for y in 0..height {
+            let y0 = y * self.height / height;
+            let y1 = ((y + 1) * self.height / height).max(y0 + 1);
+
+            for x in 0..width {
+                let x0 = x * self.width / width;
+                let x1 = ((x + 1) * self.width / width).max(x0 + 1);
+
+                let mut sum = [0u32; 3];
+                for source_y in y0..y1 {
+                    for &pixel in &self.buffer[source_y * self.width + x0..source_y * self.width + x1] {
+                        sum[0] += (pixel >> 16) & 0xFF;
+                        sum[1] += (pixel >> 8) & 0xFF;
+                        sum[2] += pixel & 0xFF;
+                    }
+                }
+
+                let count = ((x1 - x0) * (y1 - y0)) as u32;
+                target.buffer[y * width + x] = (sum[0] / count) << 16 | (sum[1] / count) << 8 | (sum[2] / count);
+            }
+        }
+    }
+}
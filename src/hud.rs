@@ -0,0 +1,42 @@
+// hud.rs
+
+use crate::framebuffer::Framebuffer;
+
+const PANEL_COLOR: u32 = 0x202020;
+const BORDER_COLOR: u32 = 0x808080;
+const TEXT_COLOR: u32 = 0xFFFFFF;
+const MARGIN: usize = 4;
+const LINE_SPACING: usize = 3;
+
+// Lines of text on a dark panel in the top left corner. Drawn over the rendered
+// image right before it goes to the window, so renders saved to files never have it.
+pub fn draw_hud(framebuffer: &mut Framebuffer, lines: &[String], scale: usize) {
+    let line_height = Framebuffer::text_size("", scale).1 + LINE_SPACING;
+    let panel_width = lines
+        .iter()
+        .map(|line| Framebuffer::text_size(line, scale).0)
+        .max()
+        .unwrap_or(0)
+        + 2 * MARGIN;
+    let panel_height = lines.len() * line_height + 2 * MARGIN - LINE_SPACING;
+
+    framebuffer.set_current_color(PANEL_COLOR);
+    framebuffer.fill_rect(MARGIN, MARGIN, panel_width, panel_height);
+    framebuffer.set_current_color(BORDER_COLOR);
+    framebuffer.rect(MARGIN, MARGIN, panel_width, panel_height);
+
+    framebuffer.set_current_color(TEXT_COLOR);
+    for (index, line) in lines.iter().enumerate() {
+        framebuffer.text(2 * MARGIN, 2 * MARGIN + index * line_height, line, scale);
+    }
+}
+
+// Cross in the middle of the screen, where fly mode aims
+pub fn draw_crosshair(framebuffer: &mut Framebuffer) {
+    let (x, y) = ((framebuffer.width / 2) as i32, (framebuffer.height / 2) as i32);
+    let size = 5;
+
+    framebuffer.set_current_color(TEXT_COLOR);
+    framebuffer.line(x - size, y, x + size, y);
+    framebuffer.line(x, y - size, x, y + size);
+}
@@ -0,0 +1,137 @@
+// instance.rs
+
+use std::sync::Arc;
+use nalgebra_glm::{Mat3, Mat4, Vec3, Vec4};
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+
+// Places a shared piece of geometry in the world through a 4x4 transform.
+// Rays are moved into object space, so the geometry itself never changes and
+// any number of instances can point to the same `Arc`.
+pub struct Instance {
+    object: Arc<dyn RayIntersect>,
+    transform: Mat4,
+    inverse: Mat4,
+    normal_matrix: Mat3,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn RayIntersect>) -> Self {
+        Instance::from_parts(object, Mat4::identity(), Mat4::identity())
+    }
+
+    // Fails for transforms that flatten the geometry, rays can't be moved into their space
+    pub fn with_transform(object: Arc<dyn RayIntersect>, transform: Mat4) -> Result<Self, String> {
+        let inverse = transform
+            .try_inverse()
+            .ok_or(String::from("Instance transform is not invertible"))?;
+        Ok(Instance::from_parts(object, transform, inverse))
+    }
+
+    fn from_parts(object: Arc<dyn RayIntersect>, transform: Mat4, inverse: Mat4) -> Self {
+        let normal_matrix = nalgebra_glm::mat4_to_mat3(&inverse).transpose();
+        Instance {
+            object,
+            transform,
+            inverse,
+            normal_matrix,
+        }
+    }
+
+    // The builders below apply on top of the current transform, so
+    // `Instance::new(g).scale(s)?.rotate(a, axis).translate(t)` reads in order.
+    // Translations and rotations can always be undone, so their inverse is built alongside.
+    pub fn translate(self, offset: Vec3) -> Self {
+        let transform = nalgebra_glm::translation(&offset) * self.transform;
+        let inverse = self.inverse * nalgebra_glm::translation(&-offset);
+        Instance::from_parts(self.object, transform, inverse)
+    }
+
+    pub fn rotate(self, angle: f32, axis: Vec3) -> Self {
+        let transform = nalgebra_glm::rotation(angle, &axis) * self.transform;
+        let inverse = self.inverse * nalgebra_glm::rotation(-angle, &axis);
+        Instance::from_parts(self.object, transform, inverse)
+    }
+
+    // A zero factor flattens the geometry and is rejected by `with_transform`
+    pub fn scale(self, factor: Vec3) -> Result<Self, String> {
+        let transform = nalgebra_glm::scaling(&factor) * self.transform;
+        Instance::with_transform(self.object, transform)
+    }
+
+    // Ray in object space, with the factor that turns object distances back into world ones
+    fn local_ray(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> (Vec3, Vec3, f32) {
+        let local_origin = (self.inverse * Vec4::new(ray_origin.x, ray_origin.y, ray_origin.z, 1.0)).xyz();
+        let local_direction = (self.inverse * Vec4::new(ray_direction.x, ray_direction.y, ray_direction.z, 0.0)).xyz();
+
+        // Primitives expect a unit direction, so keep the scale to convert distances back
+        let length = local_direction.magnitude();
+        (local_origin, local_direction / length, length)
+    }
+
+    fn to_world(&self, mut intersect: Intersect, length: f32) -> Intersect {
+        let point = intersect.point;
+        intersect.point = (self.transform * Vec4::new(point.x, point.y, point.z, 1.0)).xyz();
+        intersect.normal = (self.normal_matrix * intersect.normal).normalize();
+        intersect.distance /= length;
+        intersect
+    }
+}
+
+impl RayIntersect for Instance {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let (local_origin, local_direction, length) = self.local_ray(ray_origin, ray_direction);
+
+        let intersect = self.object.ray_intersect(&local_origin, &local_direction);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+
+        self.to_world(intersect, length)
+    }
+
+    fn is_solid(&self) -> bool {
+        self.object.is_solid()
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        let (local_origin, local_direction, length) = self.local_ray(ray_origin, ray_direction);
+
+        self.object
+            .ray_spans(&local_origin, &local_direction)
+            .into_iter()
+            .map(|span| Span::new(self.to_world(span.enter, length), self.to_world(span.exit, length)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::material::Material;
+
+    fn unit_cube() -> Arc<dyn RayIntersect> {
+        Arc::new(Cube::new(Vec3::zeros(), Vec3::new(1.0, 1.0, 1.0), Material::black()))
+    }
+
+    #[test]
+    fn singular_transforms_are_errors() {
+        assert!(Instance::with_transform(unit_cube(), Mat4::zeros()).is_err());
+        assert!(Instance::new(unit_cube()).scale(Vec3::new(1.0, 0.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn transformed_instance_is_hit_in_world_space() {
+        let instance = Instance::new(unit_cube())
+            .scale(Vec3::new(2.0, 2.0, 2.0))
+            .unwrap()
+            .rotate(std::f32::consts::FRAC_PI_2, Vec3::new(0.0, 1.0, 0.0))
+            .translate(Vec3::new(10.0, 0.0, 0.0));
+
+        // After the rotation the cube covers x in [10, 12] and z in [-2, 0]
+        let intersect = instance.ray_intersect(&Vec3::new(11.0, 1.0, 5.0), &Vec3::new(0.0, 0.0, -1.0));
+        assert!(intersect.is_intersecting);
+        assert!((intersect.distance - 5.0).abs() < 1e-4);
+        assert!((intersect.normal - Vec3::new(0.0, 0.0, 1.0)).magnitude() < 1e-4);
+    }
+}
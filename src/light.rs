@@ -3,18 +3,192 @@
 use nalgebra_glm::Vec3;
 use crate::color::Color;
 
+// Strength and falloff of the small lights standing for emissive blocks and props
+const GLOW_INTENSITY: f32 = 1.5;
+const GLOW_ATTENUATION: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightKind {
+    // Shines from `position` in every direction
+    Point { position: Vec3 },
+    // Parallel rays travelling along `direction`, like the sun
+    Directional { direction: Vec3 },
+    // Cone from `position` along `direction`. `angle` is the half-angle of the cone
+    // in radians and `penumbra` the fraction of it, from the edge, that fades out.
+    Spot { position: Vec3, direction: Vec3, angle: f32, penumbra: f32 },
+}
+
+impl LightKind {
+    // Placements `--light` picks from and the L key cycles through: the lamp above
+    // the diorama, a low sun from one side and a spotlight pointing down on the beacon
+    pub fn presets() -> [LightKind; 3] {
+        [
+            LightKind::Point { position: Vec3::new(5.0, 10.0, 5.0) },
+            LightKind::Directional { direction: Vec3::new(1.0, -0.8, 0.4).normalize() },
+            LightKind::Spot {
+                position: Vec3::new(4.0, 12.0, 4.0),
+                direction: Vec3::new(0.0, -1.0, 0.0),
+                angle: 25f32.to_radians(),
+                penumbra: 0.4,
+            },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LightKind::Point { .. } => "point",
+            LightKind::Directional { .. } => "directional",
+            LightKind::Spot { .. } => "spot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LightKind> {
+        LightKind::presets().into_iter().find(|kind| kind.name() == name)
+    }
+}
+
 pub struct Light {
-    pub position: Vec3,
+    pub kind: LightKind,
     pub color: Color,
     pub intensity: f32,
+    // Strength of the inverse-square falloff of point and spot lights, the light is
+    // divided by 1 + attenuation * distance². 0 keeps it constant with distance.
+    pub attenuation: f32,
 }
 
 impl Light {
-    pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
+    pub fn new(kind: LightKind, color: Color, intensity: f32) -> Self {
         Light {
-            position,
+            kind,
             color,
             intensity,
+            attenuation: 0.0,
         }
     }
-}
\ No newline at end of file
+
+    pub fn point(position: Vec3, color: Color, intensity: f32) -> Self {
+        Light::new(LightKind::Point { position }, color, intensity)
+    }
+
+    pub fn directional(direction: Vec3, color: Color, intensity: f32) -> Self {
+        Light::new(LightKind::Directional { direction: direction.normalize() }, color, intensity)
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, angle: f32, penumbra: f32, color: Color, intensity: f32) -> Self {
+        let direction = direction.normalize();
+        Light::new(LightKind::Spot { position, direction, angle, penumbra }, color, intensity)
+    }
+
+    // Point light standing for an emissive block or prop, fading out within a few units
+    pub fn glow(position: Vec3, color: Color) -> Self {
+        Light::point(position, color, GLOW_INTENSITY).with_attenuation(GLOW_ATTENUATION)
+    }
+
+    pub fn with_attenuation(mut self, attenuation: f32) -> Self {
+        self.attenuation = attenuation.max(0.0);
+        self
+    }
+
+    // Unit direction from `point` towards the light, the distance to it (infinite
+    // for directional lights) and the intensity that reaches the point
+    pub fn illuminate(&self, point: &Vec3) -> (Vec3, f32, f32) {
+        match self.kind {
+            LightKind::Point { position } => {
+                let (direction, distance) = direction_and_distance(point, &position);
+                (direction, distance, self.intensity * self.falloff(distance))
+            }
+            LightKind::Directional { direction } => (-direction.normalize(), f32::INFINITY, self.intensity),
+            LightKind::Spot { position, direction, angle, penumbra } => {
+                let (to_light, distance) = direction_and_distance(point, &position);
+
+                // Full inside the inner cone, fading to 0 at the edge of the outer one
+                let outer = angle.cos();
+                let inner = (angle * (1.0 - penumbra.clamp(0.0, 1.0))).cos();
+                let cosine = (-to_light).dot(&direction.normalize());
+                let cone = if inner - outer > 1e-6 {
+                    let t = ((cosine - outer) / (inner - outer)).clamp(0.0, 1.0);
+                    t * t * (3.0 - 2.0 * t)
+                } else if cosine >= outer {
+                    1.0
+                } else {
+                    0.0
+                };
+
+                (to_light, distance, self.intensity * self.falloff(distance) * cone)
+            }
+        }
+    }
+
+    fn falloff(&self, distance: f32) -> f32 {
+        1.0 / (1.0 + self.attenuation * distance * distance)
+    }
+}
+
+fn direction_and_distance(from: &Vec3, to: &Vec3) -> (Vec3, f32) {
+    let offset = to - from;
+    let distance = offset.magnitude();
+    (offset / distance.max(1e-6), distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white() -> Color {
+        Color::new(255, 255, 255)
+    }
+
+    // Spotlight at the origin pointing down, full within 15° and dark past 30°
+    fn spot() -> Light {
+        Light::spot(Vec3::zeros(), Vec3::new(0.0, -1.0, 0.0), 30f32.to_radians(), 0.5, white(), 1.0)
+    }
+
+    // Intensity of `light` 10 units below it and `degrees` away from straight down
+    fn intensity_at(light: &Light, degrees: f32) -> f32 {
+        let angle = degrees.to_radians();
+        light.illuminate(&Vec3::new(10.0 * angle.tan(), -10.0, 0.0)).2
+    }
+
+    #[test]
+    fn spot_is_cut_off_outside_its_cone() {
+        let light = spot();
+
+        assert_eq!(intensity_at(&light, 0.0), 1.0);
+        assert_eq!(intensity_at(&light, 14.0), 1.0);
+        assert_eq!(intensity_at(&light, 31.0), 0.0);
+        assert_eq!(intensity_at(&light, 60.0), 0.0);
+        // Points behind the light are never lit
+        assert_eq!(light.illuminate(&Vec3::new(0.0, 5.0, 0.0)).2, 0.0);
+    }
+
+    #[test]
+    fn spot_penumbra_fades_with_a_smoothstep() {
+        let light = spot();
+        let (outer, inner) = (30f32.to_radians().cos(), 15f32.to_radians().cos());
+        // Angle at a fraction `t` of the way from the outer to the inner cosine
+        let degrees = |t: f32| (outer + (inner - outer) * t).acos().to_degrees();
+
+        let fade: Vec<f32> = (1..10).map(|i| intensity_at(&light, degrees(i as f32 / 10.0))).collect();
+        assert!(fade.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", fade);
+        assert!((intensity_at(&light, degrees(0.5)) - 0.5).abs() < 1e-3);
+        // Flat at both edges of the penumbra, unlike a linear fade
+        assert!((intensity_at(&light, degrees(0.1)) - 0.028).abs() < 1e-3);
+        assert!((intensity_at(&light, degrees(0.9)) - 0.972).abs() < 1e-3);
+    }
+
+    #[test]
+    fn attenuation_divides_by_one_plus_a_times_the_squared_distance() {
+        let light = Light::point(Vec3::zeros(), white(), 2.0).with_attenuation(0.5);
+
+        let (direction, distance, intensity) = light.illuminate(&Vec3::new(0.0, 0.0, 2.0));
+        assert!((direction - Vec3::new(0.0, 0.0, -1.0)).magnitude() < 1e-6);
+        assert_eq!(distance, 2.0);
+        assert!((intensity - 2.0 / 3.0).abs() < 1e-6);
+
+        // Spotlights fade the same way, directional lights are infinitely far
+        let spot = spot().with_attenuation(0.5);
+        assert!((spot.illuminate(&Vec3::new(0.0, -2.0, 0.0)).2 - 1.0 / 3.0).abs() < 1e-6);
+        let sun = Light::directional(Vec3::new(0.0, -1.0, 0.0), white(), 2.0).with_attenuation(0.5);
+        assert_eq!(sun.illuminate(&Vec3::new(0.0, -100.0, 0.0)).2, 2.0);
+    }
+}
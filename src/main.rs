@@ -1,12 +1,16 @@
 // main.rs
 
-use nalgebra_glm::{Vec3, normalize};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use nalgebra_glm::Vec3;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use rand::Rng;
+use std::fs;
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
 use rayon::prelude::*;
 
 mod framebuffer;
+mod font;
+mod hud;
 mod ray_intersect;
 mod color;
 mod camera;
@@ -14,23 +18,67 @@ mod material;
 mod light;
 mod texture;
 mod cube;
+mod block_shape;
+mod bvh;
+mod triangle;
+mod mesh;
+mod obj;
+mod instance;
+mod plane;
+mod polynomial;
+mod cylinder;
+mod cone;
+mod torus;
+mod csg;
+mod render_settings;
+mod cli;
+mod animation;
+mod export;
+mod aov;
 mod diorama;
+mod debug_view;
+mod world;
+mod scene;
+mod environment;
+mod sky;
+mod ambient;
+mod fog;
+mod dynamic_resolution;
 
 use framebuffer::Framebuffer;
 use color::Color;
-use ray_intersect::{Intersect, RayIntersect};
-use camera::Camera;
-use light::Light;
+use ray_intersect::{Intersect, RayCount};
+use camera::{Camera, CameraMode, CubeFace, Projection};
+use light::{Light, LightKind};
+use render_settings::{Ambient, RenderMode, RenderSettings, Stereo};
+use debug_view::{cost_color, debug_color};
+use scene::Scene;
+use environment::Environment;
+use sky::Sky;
+use ambient::{ambient_occlusion, smooth_lighting};
+use fog::apply_fog;
+use world::{Block, World};
+use block_shape::BlockShape;
+use cli::Options;
+use animation::{turntable_step, CameraPath};
+use export::{save_exr, save_hdr, save_passes_png, save_png, timestamp, GifWriter};
+use aov::render_passes;
+use hud::{draw_crosshair, draw_hud};
+use dynamic_resolution::DynamicResolution;
 //use material::Material;
 // use texture::Texture;
-use cube::Cube;
 use diorama::{generate_diorama, generate_diorama2};
 
 const ORIGIN_BIAS: f32 = 1e-4;
-// el skybox debe tener un color azul oscuro
-const SKYBOX_COLOR: Color = Color::new(40, 24, 128);
+// Strength of the light that follows the sun of the procedural sky
+const SUN_INTENSITY: f32 = 2.5;
+// Fractions of the window size the viewer can render at, [ and ] step through them
+const RENDER_SCALES: [f32; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+// Size and minimum samples of the high quality screenshot, Shift+F2
+const SCREENSHOT_SCALE: usize = 2;
+const SCREENSHOT_SAMPLES: u32 = 16;
 
-fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
+pub fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
     let offset = intersect.normal * ORIGIN_BIAS;
     if direction.dot(&intersect.normal) < 0.0 {
         intersect.point - offset
@@ -39,12 +87,12 @@ fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
     }
 }
 
-fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+pub fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
-fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
+pub fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
     
     let (n_cosi, eta, n_normal);
 
@@ -73,10 +121,11 @@ fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
-    objects: &[Cube],
+    scene: &Scene,
+    rays: &RayCount,
 ) -> f32 {
-    let light_dir = (light.position - intersect.point).normalize();
-    //let light_distance = (light.position - intersect.point).magnitude();
+    let (light_dir, light_distance, _) = light.illuminate(&intersect.point);
+    rays.add(1);
 
     let shadow_ray_origin = offset_origin(intersect, &light_dir);
     //let mut shadow_intensity = 0.0;
@@ -91,101 +140,260 @@ fn cast_shadow(
         }
     }*/
 
-    if objects.iter().any(|object| {
+    if scene.objects().any(|object| {
         let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        shadow_intersect.is_intersecting
+        // Objects past a point or spot light do not block it
+        shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance
     }) {
-        return 1.0; 
+        1.0
     } else {
-        return 0.0; 
+        0.0
     }
      
 
     //shadow_intensity
 }
 
-pub fn cast_ray(
+// First surface hit by the ray with the ID of the block or prop it belongs to
+pub fn closest_hit(
     ray_origin: &Vec3,
     ray_direction: &Vec3,
-    objects: &[Cube],
-    light: &Light,
-    depth: u32,
-) -> Color {
-    if depth > 1 {
-        return SKYBOX_COLOR;
-    }
-
-    let mut intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
-
-    let closest = objects
-    .par_iter()
-    .filter_map(|object| {
+    scene: &Scene,
+) -> Option<(u32, Intersect)> {
+    scene
+    .par_objects()
+    .filter_map(|(id, object)| {
         let i = object.ray_intersect(ray_origin, ray_direction);
         if i.is_intersecting {
-            Some(i)
+            Some((id, i))
         } else {
             None
         }
     })
-    .min_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+    .min_by(|a, b| a.1.distance.total_cmp(&b.1.distance))
+}
 
-    if let Some(i) = closest {
-        intersect = i;
+// First surface hit by the ray, or an empty intersect if it hits nothing
+pub fn closest_intersect(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+) -> Intersect {
+    closest_hit(ray_origin, ray_direction, scene)
+        .map(|(_, intersect)| intersect)
+        .unwrap_or_else(Intersect::empty)
+}
+
+// The terms `cast_ray` adds up for a hit, each already scaled by its weight.
+// All of them are linear radiance, which can go above 1 until it is displayed.
+pub struct Shading {
+    pub emission: Vec3,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub reflection: Vec3,
+    pub refraction: Vec3,
+    pub shadow: f32, // 1 when the light is blocked
+}
+
+impl Shading {
+    pub fn total(&self) -> Vec3 {
+        self.emission + self.diffuse + self.specular + self.reflection + self.refraction
     }
+}
+
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    light: &Light,
+    settings: &RenderSettings,
+    depth: u32,
+    rays: &RayCount,
+) -> Vec3 {
+    if depth > 1 {
+        return scene.environment.sample(ray_direction);
+    }
+
+    rays.add(1);
+    let intersect = closest_intersect(ray_origin, ray_direction, scene);
 
     if !intersect.is_intersecting {
-        return SKYBOX_COLOR;
+        let sky = scene.environment.sample(ray_direction);
+        return apply_fog(sky, ray_origin, ray_direction, f32::INFINITY, scene, light, rays);
     }
 
-    // Add emission directly if the material is emissive
-    let mut result_color = intersect.material.emission;
+    let color = shade(&intersect, ray_direction, scene, light, settings, depth, rays).total();
+    apply_fog(color, ray_origin, ray_direction, intersect.distance, scene, light, rays)
+}
+
+// Image-based lighting: light from the environment reaching a diffuse surface,
+// estimated with directions importance sampled from the environment map
+fn environment_lighting(intersect: &Intersect, scene: &Scene, rays: &RayCount) -> Vec3 {
+    let environment = &scene.environment;
+    let mut rng = rand::thread_rng();
+    let mut total = Vec3::zeros();
 
-    let light_dir = (light.position - intersect.point).normalize();
-    let view_dir = (ray_origin - intersect.point).normalize();
+    for _ in 0..environment.ibl_samples {
+        let Some((direction, radiance, pdf)) = environment.sample_light(&mut rng) else {
+            return Vec3::zeros();
+        };
+        let cosine = intersect.normal.dot(&direction);
+        if cosine <= 0.0 {
+            continue;
+        }
+
+        let origin = offset_origin(intersect, &direction);
+        rays.add(1);
+        if scene.objects().any(|object| object.ray_intersect(&origin, &direction).is_intersecting) {
+            continue;
+        }
+        // Lambertian surfaces reflect cos / π of the incoming radiance
+        total += radiance * (cosine / (PI * pdf));
+    }
+
+    total / environment.ibl_samples.max(1) as f32
+}
+
+// Lighting at a surface hit, split into its terms
+pub fn shade(
+    intersect: &Intersect,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    light: &Light,
+    settings: &RenderSettings,
+    depth: u32,
+    rays: &RayCount,
+) -> Shading {
+    let (light_dir, _, incoming_intensity) = light.illuminate(&intersect.point);
+    let view_dir = -ray_direction.normalize();
     let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
 
-    let shadow_intensity = cast_shadow(&intersect, light, objects);
-    let light_intensity = light.intensity * (1.0 - shadow_intensity);
+    // Points the light does not reach, like those outside a spotlight, need no shadow ray
+    let shadow_intensity = if incoming_intensity > 0.0 { cast_shadow(intersect, light, scene, rays) } else { 0.0 };
+    let light_intensity = incoming_intensity * (1.0 - shadow_intensity);
+
+    let reflectivity = intersect.material.albedo[2];
+    let transparency = intersect.material.albedo[3];
+    // Whatever is not reflected or refracted is lit directly
+    let direct_weight = 1.0 - reflectivity - transparency;
 
-    let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
+    let diffuse_intensity = intersect.normal.dot(&light_dir).clamp(0.0, 1.0);
     let diffuse_color = intersect.material.get_diffuse_color(intersect.u, intersect.v);
-    let diffuse = diffuse_color * intersect.material.albedo[0] * diffuse_intensity * light_intensity;
+    // Light from the environment map and ambient light are added to the light from `light`
+    let ambient = match settings.ambient {
+        Ambient::Off => 0.0,
+        // Props are not on the block grid, they fall back to ray traced occlusion
+        Ambient::Smooth if scene.is_block_hit(intersect) => smooth_lighting(intersect, scene),
+        Ambient::Occlusion | Ambient::Smooth => {
+            ambient_occlusion(intersect, scene, settings.ao_radius, settings.ao_samples, rays)
+        }
+    } * settings.ambient_intensity;
+    // Sampling the environment is costly, so it is left out where it can't show or
+    // would multiply with every bounce: only camera rays of diffuse surfaces get it
+    let environment = if intersect.material.albedo[0] > 0.0 && depth == 0 {
+        environment_lighting(intersect, scene, rays)
+    } else {
+        Vec3::zeros()
+    };
+    let direct = light.color.to_vec3() * (diffuse_intensity * light_intensity);
+    let incoming = direct + Vec3::repeat(ambient) + environment;
+    let diffuse = diffuse_color.to_vec3().component_mul(&incoming) * intersect.material.albedo[0];
 
     let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
-    let specular = light.color * intersect.material.albedo[1] * specular_intensity * light_intensity;
+    let specular = light.color.to_vec3() * intersect.material.albedo[1] * specular_intensity * light_intensity;
 
-    let mut reflect_color = Color::black();
-    let reflectivity = intersect.material.albedo[2];
+    let mut reflect_color = Vec3::zeros();
     if reflectivity > 0.0 {
-        let reflect_dir = reflect(&ray_direction, &intersect.normal).normalize();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        reflect_color = cast_ray(&reflect_origin, &reflect_dir, objects, light, depth + 1);
+        let reflect_dir = reflect(ray_direction, &intersect.normal).normalize();
+        let reflect_origin = offset_origin(intersect, &reflect_dir);
+        reflect_color = cast_ray(&reflect_origin, &reflect_dir, scene, light, settings, depth + 1, rays);
     }
 
-    let mut refract_color = Color::black();
-    let transparency = intersect.material.albedo[3];
+    let mut refract_color = Vec3::zeros();
     if transparency > 0.0 {
-        let refract_dir = refract(&ray_direction, &intersect.normal, intersect.material.refractive_index);
-        let refract_origin = offset_origin(&intersect, &refract_dir);
-        refract_color = cast_ray(&refract_origin, &refract_dir, objects, light, depth + 1);
+        let refract_dir = refract(ray_direction, &intersect.normal, intersect.material.refractive_index);
+        let refract_origin = offset_origin(intersect, &refract_dir);
+        refract_color = cast_ray(&refract_origin, &refract_dir, scene, light, settings, depth + 1, rays);
+    }
+
+    Shading {
+        // Emission is added directly for emissive materials
+        emission: intersect.material.emission.to_vec3(),
+        diffuse: diffuse * direct_weight,
+        specular: specular * direct_weight,
+        reflection: reflect_color * reflectivity,
+        refraction: refract_color * transparency,
+        shadow: shadow_intensity,
+    }
+}
+
+
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    light: &Light,
+    settings: &RenderSettings,
+) {
+    // A panorama has no single view direction to offset the eyes from
+    if settings.stereo == Stereo::Off || camera.projection.is_panoramic() {
+        render_view(framebuffer, scene, camera, light, settings);
+        return;
     }
 
-    // Combine emissive color with other effects
-    result_color += (diffuse + specular) * (1.0 - reflectivity - transparency)
-        + (reflect_color * reflectivity)
-        + (refract_color * transparency);
+    let (left_eye, right_eye) = stereo_eyes(camera, settings);
+
+    match settings.stereo {
+        Stereo::Anaglyph => {
+            render_view(framebuffer, scene, &left_eye, light, settings);
+            let left_buffer = framebuffer.buffer.clone();
+            render_view(framebuffer, scene, &right_eye, light, settings);
 
-    result_color
+            for (pixel, left) in framebuffer.buffer.iter_mut().zip(left_buffer) {
+                *pixel = (left & 0xFF0000) | (*pixel & 0x00FFFF);
+            }
+        }
+        Stereo::SideBySide => {
+            // With an odd width the right eye gets the extra column
+            let width = framebuffer.width;
+            let half_width = width / 2;
+
+            for (eye, x_offset, eye_width) in [(&left_eye, 0, half_width), (&right_eye, half_width, width - half_width)] {
+                if eye_width == 0 {
+                    continue;
+                }
+                let mut eye_framebuffer = Framebuffer::new(eye_width, framebuffer.height);
+                render_view(&mut eye_framebuffer, scene, eye, light, settings);
+                for (y, row) in eye_framebuffer.buffer.chunks(eye_width).enumerate() {
+                    let start = y * width + x_offset;
+                    framebuffer.buffer[start..start + eye_width].copy_from_slice(row);
+                }
+            }
+        }
+        Stereo::Off => unreachable!(),
+    }
 }
 
+// Left and right eye cameras, half the interocular distance to each side
+fn stereo_eyes(camera: &Camera, settings: &RenderSettings) -> (Camera, Camera) {
+    let half_distance = settings.interocular_distance * 0.5;
+    (
+        camera.stereo_eye(-half_distance, settings.convergence_distance),
+        camera.stereo_eye(half_distance, settings.convergence_distance),
+    )
+}
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera, light: &Light) {
+// Renders the scene as seen from a single camera
+fn render_view(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    light: &Light,
+    settings: &RenderSettings,
+) {
     let width = framebuffer.width as f32;
     let height = framebuffer.height as f32;
     let aspect_ratio = width / height;
-    let fov = PI/3.0;
-    let perspective_scale = (fov * 0.5).tan();
 
     // Parallel iteration over rows (y-axis)
     framebuffer
@@ -193,136 +401,779 @@ pub fn render(framebuffer: &mut Framebuffer, objects: &[Cube], camera: &Camera,
         .par_chunks_mut(framebuffer.width) // Divide the buffer into rows
         .enumerate() // Keep track of row index (y)
         .for_each(|(y, row)| {
+            let mut rng = rand::thread_rng();
+
             for (x, pixel) in row.iter_mut().enumerate() {
-                // Map the pixel coordinate to screen space [-1, 1]
-                let screen_x = (2.0 * x as f32) / width - 1.0;
-                let screen_y = -(2.0 * y as f32) / height + 1.0;
+                let mut accumulated = Vec3::zeros();
 
-                // Adjust for aspect ratio and perspective
-                let screen_x = screen_x * aspect_ratio * perspective_scale;
-                let screen_y = screen_y * perspective_scale;
+                for sample in 0..settings.samples {
+                    // Jitter inside the pixel when there is more than one sample
+                    let (offset_x, offset_y) = if sample == 0 { (0.0, 0.0) } else { (rng.gen(), rng.gen()) };
 
-                // Calculate the direction of the ray for this pixel
-                let ray_direction = nalgebra_glm::normalize(&Vec3::new(screen_x, screen_y, -1.0));
+                    // Map the pixel coordinate to screen space [-1, 1]
+                    let (screen_x, screen_y) =
+                        screen_coordinates(x as f32 + offset_x, y as f32 + offset_y, width, height);
 
-                // Apply camera rotation to the ray direction
-                let rotated_direction = camera.basis_change(&ray_direction);
+                    // Build the ray for this pixel through a random point of the lens
+                    let (ray_origin, ray_direction) =
+                        camera.lens_ray(screen_x, screen_y, aspect_ratio, (rng.gen(), rng.gen()));
 
-                // Cast the ray and get the pixel color
-                let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, light, 0);
+                    // Cast the ray and get the pixel color
+                    accumulated += match settings.mode {
+                        RenderMode::Shaded => {
+                            cast_ray(&ray_origin, &ray_direction, scene, light, settings, 0, &RayCount::default())
+                        }
+                        // Shaded in full, counting every ray traced on the way
+                        RenderMode::RayCost => {
+                            let rays = RayCount::default();
+                            cast_ray(&ray_origin, &ray_direction, scene, light, settings, 0, &rays);
+                            cost_color(rays.get())
+                        }
+                        mode => debug_color(mode, &ray_origin, &ray_direction, scene, camera),
+                    };
+                }
 
-                // Set the pixel color in the framebuffer
-                *pixel = pixel_color.to_hex();
+                // Set the averaged pixel color in the framebuffer
+                *pixel = Color::from_vec3(accumulated / settings.samples as f32).to_hex();
             }
         });
 }
 
-fn main() {
-    let window_width = 500;
-    let window_height = 350;
-    let framebuffer_width = 500;
-    let framebuffer_height = 350;
-    let frame_delay = Duration::from_millis(16);
+// Maps a pixel coordinate to screen space [-1, 1], with y pointing up
+fn screen_coordinates(x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+    ((2.0 * x) / width - 1.0, -(2.0 * y) / height + 1.0)
+}
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Rust Graphics - Proyect 2 Raytracer",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    ).unwrap();
+// First surface under a framebuffer pixel, along the primary ray `render` traces for it.
+// Side-by-side stereo traces it from the eye of that half of the image. The anaglyph
+// eyes are offset symmetrically with parallel views, so the mono camera between them
+// sees what the fused image shows.
+pub fn pick(
+    framebuffer: &Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    settings: &RenderSettings,
+    x: usize,
+    y: usize,
+) -> Option<Intersect> {
+    let half_width = framebuffer.width / 2;
+    let (camera, x, width) = if settings.stereo == Stereo::SideBySide && !camera.projection.is_panoramic() {
+        let (left_eye, right_eye) = stereo_eyes(camera, settings);
+        if x < half_width {
+            (left_eye, x, half_width)
+        } else {
+            (right_eye, x - half_width, framebuffer.width - half_width)
+        }
+    } else {
+        (camera.clone(), x, framebuffer.width)
+    };
 
-    // move the window around
-    window.set_position(500, 500);
-    window.update();
+    let width = width as f32;
+    let height = framebuffer.height as f32;
+    let (screen_x, screen_y) = screen_coordinates(x as f32, y as f32, width, height);
 
-    // Variable for the diorama
-    let election = 1;
-    let objects: Vec<Cube>;
+    let (ray_origin, ray_direction) = camera.primary_ray(screen_x, screen_y, width / height);
+    let intersect = closest_intersect(&ray_origin, &ray_direction, scene);
+
+    if intersect.is_intersecting {
+        Some(intersect)
+    } else {
+        None
+    }
+}
+
+// Turns `light` into the sun of a procedural sky, with its color and strength
+fn follow_sun(scene: &Scene, light: &mut Light) {
+    if let Some((direction, color, daylight)) = scene.environment.sun() {
+        light.kind = LightKind::Directional { direction: -direction };
+        light.color = Color::from_vec3(color);
+        light.intensity = SUN_INTENSITY * daylight;
+    }
+}
+
+// Framebuffer pixel under a point of the window, which may have a different size
+fn window_to_pixel((mouse_x, mouse_y): (f32, f32), window: &Window, framebuffer: &Framebuffer) -> (usize, usize) {
+    let (window_width, window_height) = window.get_size();
+    let x = (mouse_x / window_width.max(1) as f32 * framebuffer.width as f32) as usize;
+    let y = (mouse_y / window_height.max(1) as f32 * framebuffer.height as f32) as usize;
+    (x, y)
+}
+
+// Steps through `RENDER_SCALES` from the closest one to `scale`
+fn change_render_scale(scale: f32, step: i32) -> f32 {
+    let closest = RENDER_SCALES
+        .iter()
+        .enumerate()
+        .min_by(|a, b| (a.1 - scale).abs().total_cmp(&(b.1 - scale).abs()))
+        .unwrap()
+        .0;
+    RENDER_SCALES[(closest as i32 + step).clamp(0, RENDER_SCALES.len() as i32 - 1) as usize]
+}
+
+// Renders a still, a turntable or a camera path to files instead of the window
+fn render_headless(
+    options: &Options,
+    scene: &mut Scene,
+    camera: &mut Camera,
+    light: &mut Light,
+    settings: &RenderSettings,
+) -> Result<(), String> {
+    let animated = options.turntable.is_some() || options.path.is_some() || options.day_cycle.is_some();
+    if options.day_cycle.is_some() && scene.environment.get_sky().is_none() {
+        return Err(String::from("A day cycle needs the procedural sky, use --sky"));
+    }
+
+    // A path like render.png would otherwise become a directory of frames or faces
+    let image_output = [".png", ".jpg", ".jpeg", ".exr", ".hdr", ".gif"]
+        .iter()
+        .any(|extension| options.output.to_lowercase().ends_with(extension));
+
+    if let Projection::Cubemap(_) = options.projection {
+        if animated {
+            return Err(String::from("Cubemaps can only be rendered as stills"));
+        }
+        if image_output {
+            return Err(format!("Cubemap faces are saved to a directory, not {}", options.output));
+        }
+        return render_cubemap(options, scene, camera, light, settings);
+    }
+
+    // Float outputs come from the passes, which keep the unclamped linear radiance
+    let float_output = options.output.ends_with(".exr") || options.output.ends_with(".hdr");
+    if options.aov || float_output {
+        if animated {
+            return Err(String::from("Render passes and float images can only be rendered as stills"));
+        }
+        // Passes always hold the shaded image from the single camera
+        if settings.mode != RenderMode::Shaded || settings.stereo != Stereo::Off {
+            return Err(String::from("Render passes and float images are shaded and mono, drop --mode and --stereo"));
+        }
+        let passes = render_passes(options.width, options.height, scene, camera, light, settings);
+        return if options.output.ends_with(".exr") {
+            save_exr(&passes, &options.output, options.aov, options.precision)
+        } else if options.output.ends_with(".hdr") {
+            if options.aov {
+                return Err(String::from("Radiance .hdr files only hold the beauty pass, use .exr for passes"));
+            }
+            save_hdr(&passes, &options.output)
+        } else if options.output.ends_with(".png") {
+            Err(String::from("Render passes need a directory or an .exr file as output"))
+        } else {
+            save_passes_png(&passes, &options.output)
+        };
+    }
+
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+
+    let path = match &options.path {
+        Some(file_path) => Some(CameraPath::load(file_path)?),
+        None => None,
+    };
+    let frames = match (&path, options.turntable.or(options.day_cycle)) {
+        (Some(_), _) => options.frames.max(1),
+        (None, Some(frames)) => frames.max(1),
+        (None, None) => {
+            render(&mut framebuffer, scene, camera, light, settings);
+            return save_png(&framebuffer, &options.output);
+        }
+    };
+
+    if image_output && !options.output.ends_with(".gif") {
+        return Err(format!("Animations are saved to a directory or a .gif file, not {}", options.output));
+    }
+
+    let mut gif = if options.output.ends_with(".gif") {
+        Some(GifWriter::create(&options.output, options.fps)?)
+    } else {
+        fs::create_dir_all(&options.output).map_err(|e| format!("Failed to create {}: {}", options.output, e))?;
+        None
+    };
+
+    for frame in 0..frames {
+        match &path {
+            Some(path) => path.apply(camera, path.duration() * frame as f32 / (frames - 1).max(1) as f32),
+            None if frame > 0 && options.turntable.is_some() => turntable_step(camera, frames),
+            None => {}
+        }
+        // The whole day is spread over the frames, along with any camera animation
+        if options.day_cycle.is_some() && frame > 0 {
+            scene.environment.advance_time(24.0 / frames as f32);
+            if options.light.is_none() {
+                follow_sun(scene, light);
+            }
+        }
+
+        render(&mut framebuffer, scene, camera, light, settings);
+
+        match gif.as_mut() {
+            Some(gif) => gif.add_frame(&framebuffer)?,
+            None => save_png(&framebuffer, &format!("{}/frame_{:04}.png", options.output, frame))?,
+        }
+        println!("Rendered frame {}/{}", frame + 1, frames);
+    }
 
-    if election == 1 {
+    Ok(())
+}
+
+// Renders the six faces around the eye as square images named after each face
+fn render_cubemap(
+    options: &Options,
+    scene: &Scene,
+    camera: &mut Camera,
+    light: &Light,
+    settings: &RenderSettings,
+) -> Result<(), String> {
+    let mut framebuffer = Framebuffer::new(options.width, options.width);
+    fs::create_dir_all(&options.output).map_err(|e| format!("Failed to create {}: {}", options.output, e))?;
+
+    for face in CubeFace::ALL {
+        camera.projection = Projection::Cubemap(face);
+        render(&mut framebuffer, scene, camera, light, settings);
+        save_png(&framebuffer, &format!("{}/{}.png", options.output, face.name()))?;
+        println!("Rendered face {}", face.name());
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let options = Options::from_args().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    });
+
+    let frame_delay = Duration::from_millis(16);
+
+    // Variable for the diorama
+    let election = options.scene;
+    let mut scene = if election == 1 {
         // Generate scene
-        objects = generate_diorama();
+        generate_diorama()
     } else {
         // Generate scene
-        objects = generate_diorama2();
+        generate_diorama2()
+    };
+
+    // A saved world replaces the blocks of the diorama
+    let world_path = options.world.clone().unwrap_or_else(|| String::from("world.scene"));
+    if options.world.is_some() {
+        match World::load(&world_path, scene.palette.len()) {
+            Ok(world) => scene.set_world(world),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
     }
 
+    // Environment map or procedural sky seen by missed rays, the flat skybox color without them
+    let environment = match (&options.environment, options.sky_time) {
+        (Some(path), _) => Environment::load(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }),
+        (None, Some(time)) => Environment::sky(Sky::new(time, options.turbidity)),
+        (None, None) => Environment::default(),
+    };
+    scene.environment = environment
+        .with_rotation(options.environment_rotation)
+        .with_intensity(options.environment_intensity)
+        .with_ibl(options.ibl_samples);
+    scene.fog = options.fog;
+
     // Initialize camera
     let mut camera = Camera::new(
         Vec3::new(10.0, 15.0, 15.0),  // eye: Initial camera position
         Vec3::new(2.5, 3.0, 2.5),  // center: Point the camera is looking at (origin)
         Vec3::new(0.0, 1.0, 0.0)   // up: World up vector
     );
+    camera.set_projection(options.projection);
+
+    let rotation_speed = PI / 2.0;  // Radians per second
+    let zoom_speed = 5.0;           // Units per second
+    let view_size_speed = 0.05;
+    let aperture_speed = 0.02;
+    let interocular_speed = 0.01;
+    let convergence_speed = 0.5;
+    let fly_speed = 4.0;          // Units per second
+    let look_speed = PI / 2.0;    // Radians per second
+    let mouse_sensitivity = 0.005; // Radians per pixel
+    let environment_rotation_speed = PI / 4.0; // Radians per second
+    let environment_intensity_speed = 0.03;
+    let ambient_intensity_speed = 0.5; // Per second
+    let time_speed = 2.0;         // Hours per second
+
+    let mut last_frame = Instant::now();
+    let mut last_mouse_position: Option<(f32, f32)> = None;
+    // Mouse look in fly mode, toggled with C so the cursor can still be used otherwise
+    let mut mouse_look = false;
+
+    // Block editing
+    let mut build_mode = false;
+    let mut selected_block: u8 = 1;
+    let mut mouse_was_down = (false, false);
+
+    // On-screen information
+    let mut show_hud = true;
+    let mut fps = 0.0;
 
-    let rotation_speed = PI/10.0;
-    let zoom_speed = 0.5;
+    let mut settings = RenderSettings {
+        samples: options.samples.max(1),
+        mode: options.mode,
+        stereo: options.stereo,
+        ambient: options.ambient,
+        ..RenderSettings::default()
+    };
+    if let Some(distance) = options.interocular_distance {
+        settings.interocular_distance = distance;
+    }
+    if let Some(distance) = options.convergence_distance {
+        settings.convergence_distance = distance;
+    }
+    if let Some(intensity) = options.ambient_intensity {
+        settings.ambient_intensity = intensity.max(0.0);
+    }
+    if let Some(radius) = options.ao_radius {
+        settings.ao_radius = radius;
+    }
+    if let Some(samples) = options.ao_samples {
+        settings.ao_samples = samples.max(1);
+    }
 
     // Initialize light
     let mut light = Light::new(
-        Vec3::new(5.0, 10.0, 5.0),
+        options.light.unwrap_or(LightKind::presets()[0]),
         Color::new(255, 255, 200),
         2.5
-    );
+    ).with_attenuation(options.attenuation);
+    // Restored when the L key takes the light away from the sun
+    let (lamp_color, lamp_intensity) = (light.color, light.intensity);
+
+    // With a procedural sky the light starts as its sun, until L picks a preset,
+    // unless --light already picked one
+    let mut follows_sun = scene.environment.get_sky().is_some() && options.light.is_none();
+    if follows_sun {
+        follow_sun(&scene, &mut light);
+    }
+    scene.environment.set_sun_light(follows_sun);
+
+    if options.headless {
+        if let Err(message) = render_headless(&options, &mut scene, &mut camera, &mut light, &settings) {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // The framebuffer is rendered at `render_scale` of the window size and then
+    // resampled into `display`, which is what the window shows
+    let mut render_scale = options.scale;
+    let mut dynamic_resolution = DynamicResolution::new(options.target_fps);
+    let mut framebuffer = Framebuffer::new(options.width, options.height);
+    let mut display = Framebuffer::new(options.width, options.height);
+    let mut window = Window::new(
+        "Rust Graphics - Proyect 2 Raytracer",
+        options.width,
+        options.height,
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        },
+    ).unwrap_or_else(|e| {
+        eprintln!("Failed to open the window: {}", e);
+        std::process::exit(1);
+    });
 
-    // Frame counter
-    let mut frame = 0; // Initialize the frame counter
+    // move the window around
+    window.set_position(500, 500);
+    window.update();
 
     while window.is_open() {
         // listen to inputs
         if window.is_key_down(Key::Escape) {
             break;
         }
-        //  camera orbit controls
-        if window.is_key_down(Key::Left) {
-            camera.orbit(rotation_speed, 0.0);
+        // Time since the last frame, so flying speed does not depend on the render time
+        let now = Instant::now();
+        let delta_time = now.duration_since(last_frame).as_secs_f32().min(0.5);
+        last_frame = now;
+        // Smoothed so the number can be read
+        fps = if fps == 0.0 { 1.0 / delta_time } else { fps * 0.9 + 0.1 / delta_time };
+
+        if window.is_key_pressed(Key::Tab, KeyRepeat::No) {
+            camera.toggle_mode();
+        }
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            mouse_look = !mouse_look;
+        }
+        let mouse_look = mouse_look && camera.mode == CameraMode::Fly;
+        window.set_cursor_visibility(!mouse_look);
+
+        // Render scale
+        if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+            render_scale = change_render_scale(render_scale, -1);
+        }
+        if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+            render_scale = change_render_scale(render_scale, 1);
+        }
+
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            dynamic_resolution.toggle();
+        }
+
+        let mouse_position = window.get_mouse_pos(MouseMode::Pass);
+
+        match camera.mode {
+            CameraMode::Orbit => {
+                //  camera orbit controls
+                let rotation_speed = rotation_speed * delta_time;
+                if window.is_key_down(Key::Left) {
+                    camera.orbit(rotation_speed, 0.0);
+                }
+                if window.is_key_down(Key::Right) {
+                    camera.orbit(-rotation_speed, 0.0);
+                }
+                if window.is_key_down(Key::Up) {
+                    camera.orbit(0.0, -rotation_speed);
+                }
+                if window.is_key_down(Key::Down) {
+                    camera.orbit(0.0, rotation_speed);
+                }
+
+                // camera zoom controls
+                let zoom_speed = zoom_speed * delta_time;
+                if window.is_key_down(Key::Q) {
+                    camera.zoom(zoom_speed);
+                }
+                if window.is_key_down(Key::E) {
+                    camera.zoom(-zoom_speed);
+                }
+            }
+            CameraMode::Fly => {
+                // Shift to go faster, Ctrl to go slower
+                let mut speed = fly_speed * delta_time;
+                if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+                    speed *= 3.0;
+                }
+                if window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl) {
+                    speed *= 0.3;
+                }
+
+                let axis = |positive: Key, negative: Key| {
+                    window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+                };
+                let forward = axis(Key::W, Key::S);
+                let right = axis(Key::D, Key::A);
+                let up = axis(Key::E, Key::Q);
+                if forward != 0.0 || right != 0.0 || up != 0.0 {
+                    camera.fly(forward * speed, right * speed, up * speed);
+                }
+
+                // Mouse look while it is toggled on, with the arrows as an alternative
+                if let (true, Some((x, y)), Some((last_x, last_y))) = (mouse_look, mouse_position, last_mouse_position) {
+                    if x != last_x || y != last_y {
+                        camera.look((x - last_x) * mouse_sensitivity, (last_y - y) * mouse_sensitivity);
+                    }
+                }
+                let look_speed = look_speed * delta_time;
+                let yaw = axis(Key::Right, Key::Left);
+                let pitch = axis(Key::Up, Key::Down);
+                if yaw != 0.0 || pitch != 0.0 {
+                    camera.look(yaw * look_speed, pitch * look_speed);
+                }
+            }
+        }
+
+        last_mouse_position = mouse_position;
+
+        // camera projection controls
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            camera.set_projection(camera.projection.next());
+        }
+        if window.is_key_down(Key::Z) {
+            camera.change_view_size(1.0 - view_size_speed);
+        }
+        if window.is_key_down(Key::X) {
+            camera.change_view_size(1.0 + view_size_speed);
+        }
+
+        // Environment map rotation and brightness
+        if window.is_key_down(Key::Home) {
+            scene.environment.rotate(-environment_rotation_speed * delta_time);
+            if follows_sun {
+                follow_sun(&scene, &mut light);
+            }
+        }
+        if window.is_key_down(Key::End) {
+            scene.environment.rotate(environment_rotation_speed * delta_time);
+            if follows_sun {
+                follow_sun(&scene, &mut light);
+            }
+        }
+
+        // Time of day of the procedural sky
+        if window.is_key_down(Key::Semicolon) {
+            scene.environment.advance_time(-time_speed * delta_time);
+            if follows_sun {
+                follow_sun(&scene, &mut light);
+            }
+        }
+        if window.is_key_down(Key::Apostrophe) {
+            scene.environment.advance_time(time_speed * delta_time);
+            if follows_sun {
+                follow_sun(&scene, &mut light);
+            }
+        }
+        if window.is_key_down(Key::PageUp) {
+            scene.environment.change_intensity(1.0 + environment_intensity_speed);
+        }
+        if window.is_key_down(Key::PageDown) {
+            scene.environment.change_intensity(1.0 - environment_intensity_speed);
+        }
+
+        // depth of field controls
+        if window.is_key_down(Key::Minus) {
+            camera.change_aperture(-aperture_speed);
+        }
+        if window.is_key_down(Key::Equal) {
+            camera.change_aperture(aperture_speed);
+        }
+        if window.is_key_pressed(Key::Comma, KeyRepeat::No) {
+            settings.change_samples(-1);
+        }
+        if window.is_key_pressed(Key::Period, KeyRepeat::No) {
+            settings.change_samples(1);
+        }
+
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            settings.mode = settings.mode.next();
+        }
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            settings.ambient = settings.ambient.next();
+        }
+        if window.is_key_down(Key::Y) {
+            settings.change_ambient_intensity(ambient_intensity_speed * delta_time);
+        }
+        if window.is_key_down(Key::H) {
+            settings.change_ambient_intensity(-ambient_intensity_speed * delta_time);
+        }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            scene.fog.toggle();
+        }
+
+        // stereo controls
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            settings.stereo = settings.stereo.next();
+        }
+        if window.is_key_down(Key::J) {
+            settings.change_interocular_distance(-interocular_speed);
         }
-        if window.is_key_down(Key::Right) {
-            camera.orbit(-rotation_speed, 0.0);
+        if window.is_key_down(Key::K) {
+            settings.change_interocular_distance(interocular_speed);
         }
-        if window.is_key_down(Key::Up) {
-            camera.orbit(0.0, -rotation_speed);
+        if window.is_key_down(Key::U) {
+            settings.change_convergence_distance(-convergence_speed);
         }
-        if window.is_key_down(Key::Down) {
-            camera.orbit(0.0, rotation_speed);
+        if window.is_key_down(Key::I) {
+            settings.change_convergence_distance(convergence_speed);
         }
 
-        // camera zoom controls
-        if window.is_key_down(Key::Q) {
-            camera.zoom(zoom_speed);
+        // Focus on whatever is under the cursor
+        if window.is_key_pressed(Key::F, KeyRepeat::No) || window.get_mouse_down(MouseButton::Middle) {
+            if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard).map(|mouse| window_to_pixel(mouse, &window, &framebuffer)) {
+                if let Some(intersect) = pick(&framebuffer, &scene, &camera, &settings, x, y) {
+                    camera.focus_on(&intersect.point);
+                }
+            }
+        }
+
+        // Build mode: left click removes the block under the cursor, right click
+        // places the selected block against the face under it
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            build_mode = !build_mode;
+            println!("Build mode: {}", if build_mode { "on" } else { "off" });
         }
-        if window.is_key_down(Key::E) {
-            camera.zoom(-zoom_speed);
+        if build_mode {
+            let block_keys = [
+                Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
+                Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::Key0,
+            ];
+            let mut new_selection = block_keys
+                .iter()
+                .position(|key| window.is_key_pressed(*key, KeyRepeat::No))
+                .map(|index| index as u8 + 1);
+            // The mouse wheel goes through the whole palette
+            if let Some((_, scroll)) = window.get_scroll_wheel() {
+                let count = scene.palette.len() as i32;
+                let step = if scroll > 0.0 { 1 } else { -1 };
+                new_selection = Some(((selected_block as i32 - 1 + step).rem_euclid(count) + 1) as u8);
+            }
+            if let Some(block) = new_selection.filter(|block| (*block as usize) <= scene.palette.len()) {
+                selected_block = block;
+                println!("Selected block: {}", scene.palette[block as usize - 1].0);
+            }
+
+            let mouse_down = (window.get_mouse_down(MouseButton::Left), window.get_mouse_down(MouseButton::Right));
+            let remove = mouse_down.0 && !mouse_was_down.0;
+            let place = mouse_down.1 && !mouse_was_down.1;
+            mouse_was_down = mouse_down;
+
+            if remove || place {
+                if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard).map(|mouse| window_to_pixel(mouse, &window, &framebuffer)) {
+                    if let Some(intersect) = pick(&framebuffer, &scene, &camera, &settings, x, y) {
+                        if remove {
+                            scene.remove_block_at(&intersect);
+                        } else {
+                            scene.place_block_at(&intersect, Block::new(selected_block, BlockShape::Full));
+                        }
+                    }
+                }
+            }
         }
 
-        // Change light color
-        if window.is_key_down(Key::Key1) {  
-            light.color = Color::new(255, 223, 128); // Warm light
-            //println!("Current light color: {:?}", light.color);
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            show_hud = !show_hud;
         }
-        if window.is_key_down(Key::Key2) {  
-            light.color = Color::new(128, 128, 255); // Cool light
-            //println!("Current light color: {:?}", light.color);
+
+        // Save the edited blocks to the scene format
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            match scene.world.save(&world_path) {
+                Ok(()) => println!("Saved world to {}", world_path),
+                Err(message) => eprintln!("{}", message),
+            }
         }
-        if window.is_key_down(Key::Key3) {  
-            light.color = Color::new(128, 255, 128); // Greenish light
-            //println!("Current light color: {:?}", light.color);
+
+        // Light type, after the last preset the sky gets its sun back
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            let presets = LightKind::presets();
+            let next = if follows_sun {
+                0
+            } else {
+                presets.iter().position(|kind| kind.name() == light.kind.name()).unwrap() + 1
+            };
+
+            if next == presets.len() && scene.environment.get_sky().is_some() {
+                follows_sun = true;
+                follow_sun(&scene, &mut light);
+            } else {
+                if follows_sun {
+                    light.color = lamp_color;
+                    light.intensity = lamp_intensity;
+                }
+                follows_sun = false;
+                light.kind = presets[next % presets.len()];
+            }
+            scene.environment.set_sun_light(follows_sun);
         }
-        if window.is_key_down(Key::Key4) {  
-            light.color = Color::new(255, 255, 255); // White light
-            //println!("Current light color: {:?}", light.color);
+
+        // Change light color, the number keys pick blocks in build mode
+        if !build_mode {
+            if window.is_key_down(Key::Key1) {  
+                light.color = Color::new(255, 223, 128); // Warm light
+                //println!("Current light color: {:?}", light.color);
+            }
+            if window.is_key_down(Key::Key2) {  
+                light.color = Color::new(128, 128, 255); // Cool light
+                //println!("Current light color: {:?}", light.color);
+            }
+            if window.is_key_down(Key::Key3) {  
+                light.color = Color::new(128, 255, 128); // Greenish light
+                //println!("Current light color: {:?}", light.color);
+            }
+            if window.is_key_down(Key::Key4) {  
+                light.color = Color::new(255, 255, 255); // White light
+                //println!("Current light color: {:?}", light.color);
+            }
         }
 
         // Only render every N frames to improve responsiveness
         //if frame % 5 == 0 {
+        // Follow the window size, reallocating the buffers when it changes
+        let (window_width, window_height) = window.get_size();
+        let (window_width, window_height) = (window_width.max(1), window_height.max(1));
+        // and rendering fewer pixels while the camera moves if frames are too slow
+        let moving = camera.is_changed();
+        let scale = render_scale * dynamic_resolution.factor(moving);
+        let render_width = ((window_width as f32 * scale) as usize).max(1);
+        let render_height = ((window_height as f32 * scale) as usize).max(1);
+        if (framebuffer.width, framebuffer.height) != (render_width, render_height) {
+            framebuffer = Framebuffer::new(render_width, render_height);
+        }
+        if (display.width, display.height) != (window_width, window_height) {
+            display = Framebuffer::new(window_width, window_height);
+        }
+
         framebuffer.clear();
-        render(&mut framebuffer, &objects, &camera, &light);
+        let render_start = Instant::now();
+        render(&mut framebuffer, &scene, &camera, &light, &settings);
+        dynamic_resolution.update(render_start.elapsed().as_secs_f32(), moving);
         //}
         //frame += 1;
 
+        // Screenshot of the view before the HUD is drawn over it, at the window size
+        // whatever the render scale or dynamic resolution. With Shift it is rendered
+        // at a higher resolution and sample count.
+        if window.is_key_pressed(Key::F2, KeyRepeat::No) {
+            let file_path = format!("screenshot_{}.png", timestamp());
+            let result = if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+                let mut screenshot =
+                    Framebuffer::new(display.width * SCREENSHOT_SCALE, display.height * SCREENSHOT_SCALE);
+                let screenshot_settings = RenderSettings {
+                    samples: settings.samples.max(SCREENSHOT_SAMPLES),
+                    ..settings
+                };
+                render(&mut screenshot, &scene, &camera, &light, &screenshot_settings);
+                save_png(&screenshot, &file_path)
+            } else if (framebuffer.width, framebuffer.height) != (display.width, display.height) {
+                let mut screenshot = Framebuffer::new(display.width, display.height);
+                render(&mut screenshot, &scene, &camera, &light, &settings);
+                save_png(&screenshot, &file_path)
+            } else {
+                save_png(&framebuffer, &file_path)
+            };
+            match result {
+                Ok(()) => println!("Saved {}", file_path),
+                Err(message) => eprintln!("{}", message),
+            }
+        }
+
+        framebuffer.resample_into(&mut display);
+
+        if show_hud {
+            let (name, _) = &scene.palette[selected_block as usize - 1];
+            let hud_lines = [
+                format!("FPS {:.1}", fps),
+                format!("CAMERA {:.1} {:.1} {:.1}", camera.eye.x, camera.eye.y, camera.eye.z),
+                format!("LIGHT {} {} {} {}", light.kind.name(), light.color.r, light.color.g, light.color.b),
+                match scene.environment.get_sky() {
+                    Some(sky) => format!("TIME {:02}:{:02}", sky.time as u32, (sky.time.fract() * 60.0) as u32),
+                    None => String::from("TIME -"),
+                },
+                format!("SAMPLES {}", settings.samples),
+                format!("SCALE {:.2} ({}X{})", render_scale, framebuffer.width, framebuffer.height),
+                if dynamic_resolution.enabled {
+                    format!("DYNAMIC {:.0} FPS", dynamic_resolution.target_fps)
+                } else {
+                    String::from("DYNAMIC OFF")
+                },
+                format!("MODE {}", settings.mode.name()),
+                format!("AMBIENT {} {:.2}", settings.ambient.name(), settings.ambient_intensity),
+                if scene.fog.enabled {
+                    format!("FOG {:.3}", scene.fog.density)
+                } else {
+                    String::from("FOG OFF")
+                },
+                format!("BLOCK {}{}", name, if build_mode { " (BUILD: 1-0 PICK BLOCKS)" } else { "" }),
+            ];
+            draw_hud(&mut display, &hud_lines, 1);
+            if camera.mode == CameraMode::Fly {
+                draw_crosshair(&mut display);
+            }
+        }
+
         // Update the window with the rendered frame
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+            .update_with_buffer(&display.buffer, display.width, display.height)
             .unwrap();
 
         std::thread::sleep(frame_delay);
@@ -1,6 +1,8 @@
 // material.rs
 
 //use once_cell::sync::Lazy;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use nalgebra_glm::Vec3;
 use crate::color::Color;
@@ -38,6 +40,23 @@ impl Material {
     }
 }
 
+    // Number that tells materials apart, equal for materials with the same
+    // properties and the same texture. A texture is told apart by its allocation,
+    // so blocks sharing a palette entry match but separately loaded copies don't.
+    pub fn id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for color in [self.diffuse, self.emission] {
+            (color.r, color.g, color.b).hash(&mut hasher);
+        }
+        for value in self.albedo.iter().chain([self.specular, self.refractive_index].iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        for texture in [&self.texture, &self.normal_map] {
+            texture.as_ref().map(|texture| Arc::as_ptr(texture) as usize).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
     pub fn get_diffuse_color(&self, u: f32, v: f32) -> Color {
         if let Some(texture) = &self.texture {
             let x = (u * (texture.width as f32 - 1.0)) as usize;
@@ -66,6 +85,7 @@ impl Material {
     }
 
     // Generic Material
+    #[allow(clippy::self_named_constructors)]
     pub fn material() -> Self {
         Material {
             diffuse: Color::new(255, 255, 255),
@@ -248,5 +268,59 @@ impl Material {
         }
     }
 
-	
+    pub fn beacon_beam() -> Self {
+        Material {
+            diffuse: Color::new(120, 255, 255),
+            specular: 1.0,
+            albedo: [0.3, 0.0, 0.0, 0.6],
+            refractive_index: 1.0,
+            has_texture: false,
+            has_normal_map: false,
+            texture: None,
+            normal_map: None,
+            emission: Color::new(40, 160, 160),
+        }
+    }
+
+    pub fn gold() -> Self {
+        Material {
+            diffuse: Color::new(250, 200, 60),
+            specular: 80.0,
+            albedo: [0.6, 0.6, 0.3, 0.0],
+            refractive_index: 0.0,
+            has_texture: false,
+            has_normal_map: false,
+            texture: None,
+            normal_map: None,
+            emission: Color::black(),
+        }
+    }
+
+    pub fn oak() -> Self {
+        Material {
+            diffuse: Color::new(160, 125, 75),
+            specular: 5.0,
+            albedo: [0.8, 0.1, 0.0, 0.0],
+            refractive_index: 0.0,
+            has_texture: false,
+            has_normal_map: false,
+            texture: None,
+            normal_map: None,
+            emission: Color::black(),
+        }
+    }
+
+    pub fn torch_flame() -> Self {
+        Material {
+            diffuse: Color::new(255, 200, 80),
+            specular: 1.0,
+            albedo: [0.5, 0.0, 0.0, 0.0],
+            refractive_index: 0.0,
+            has_texture: false,
+            has_normal_map: false,
+            texture: None,
+            normal_map: None,
+            emission: Color::new(255, 170, 40),
+        }
+    }
 }
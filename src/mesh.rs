@@ -0,0 +1,149 @@
+// mesh.rs
+
+use std::collections::HashMap;
+use nalgebra_glm::Vec3;
+use crate::bvh::{Aabb, Bvh};
+use crate::ray_intersect::{Intersect, RayIntersect, Span};
+use crate::triangle::Triangle;
+
+// A triangle soup behind a single BVH. Several OBJ files can be merged into
+// one mesh so that all the props of a scene share the same tree.
+pub struct Mesh {
+    triangles: Vec<Triangle>,
+    bvh: Bvh,
+    bounds: Aabb,
+    // Every edge is shared by exactly two triangles, so the mesh encloses a volume
+    closed: bool,
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Self {
+        let bounds: Vec<Aabb> = triangles.iter().map(|triangle| triangle.bounds()).collect();
+        let bvh = Bvh::build(&bounds);
+        let closed = is_closed(&triangles);
+        let bounds = bounds.iter().fold(Aabb::empty(), |total, bounds| total.union(bounds));
+        Mesh { triangles, bvh, bounds, closed }
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let mut closest: Option<(usize, f32, f32, f32)> = None;
+
+        self.bvh.traverse(ray_origin, ray_direction, |index| {
+            let (t, u, v) = self.triangles[index].hit(ray_origin, ray_direction)?;
+            if closest.is_none_or(|(_, best, _, _)| t < best) {
+                closest = Some((index, t, u, v));
+            }
+            Some(t)
+        });
+
+        match closest {
+            Some((index, t, u, v)) => self.triangles[index].intersect_at(ray_origin, ray_direction, t, u, v),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn is_solid(&self) -> bool {
+        self.closed
+    }
+
+    // Every crossing along the ray, paired up in order. The walk starts from a point
+    // moved back outside the bounds, as the BVH only reports hits in front of it.
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        let corner_distance = (self.bounds.min - ray_origin).abs().sup(&(self.bounds.max - ray_origin).abs()).magnitude();
+        let offset = corner_distance + 1.0;
+        let start = ray_origin - ray_direction * offset;
+
+        let mut crossings: Vec<(usize, f32, f32, f32)> = Vec::new();
+        self.bvh.traverse(&start, ray_direction, |index| {
+            if let Some((t, u, v)) = self.triangles[index].crossing(&start, ray_direction) {
+                crossings.push((index, t - offset, u, v));
+            }
+            // No distance back, so every leaf along the ray is visited
+            None
+        });
+        crossings.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        // A ray through an edge crosses both triangles next to it at the same distance
+        crossings.dedup_by(|b, a| (b.1 - a.1).abs() < 1e-5);
+
+        crossings
+            .chunks_exact(2)
+            .map(|pair| {
+                let [enter, exit] = [pair[0], pair[1]].map(|(index, t, u, v)| {
+                    self.triangles[index].intersect_at(ray_origin, ray_direction, t, u, v)
+                });
+                Span::new(enter, exit)
+            })
+            .collect()
+    }
+}
+
+// Closed when every edge, by vertex positions, belongs to exactly two triangles
+fn is_closed(triangles: &[Triangle]) -> bool {
+    let key = |position: &Vec3| [position.x.to_bits(), position.y.to_bits(), position.z.to_bits()];
+    let mut edges: HashMap<([u32; 3], [u32; 3]), u32> = HashMap::new();
+
+    for triangle in triangles {
+        let corners = triangle.vertices.map(|vertex| key(&vertex.position));
+        for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+            let edge = if corners[a] < corners[b] { (corners[a], corners[b]) } else { (corners[b], corners[a]) };
+            *edges.entry(edge).or_insert(0) += 1;
+        }
+    }
+
+    !edges.is_empty() && edges.values().all(|count| *count == 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+    use crate::material::Material;
+    use crate::triangle::Vertex;
+
+    // Unit cube from the origin, two triangles per face wound outwards
+    fn cube_triangles() -> Vec<Triangle> {
+        let corner = |i: usize| Vec3::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32);
+        let faces = [[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]];
+
+        faces
+            .iter()
+            .flat_map(|face| [[face[0], face[1], face[2]], [face[0], face[2], face[3]]])
+            .map(|indices| {
+                let p = indices.map(corner);
+                let normal = (p[1] - p[0]).cross(&(p[2] - p[0])).normalize();
+                Triangle::new(p.map(|position| Vertex::new(position, normal, Vec2::zeros())), Material::black())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn closed_and_open_meshes() {
+        let mut triangles = cube_triangles();
+        assert!(Mesh::new(cube_triangles()).is_solid());
+
+        triangles.pop();
+        assert!(!Mesh::new(triangles).is_solid());
+    }
+
+    #[test]
+    fn spans_include_crossings_behind_the_origin() {
+        let mesh = Mesh::new(cube_triangles());
+
+        // From inside the cube, the entry lies behind the origin
+        let spans = mesh.ray_spans(&Vec3::new(0.3, 0.5, 0.5), &Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.distance + 0.3).abs() < 1e-4);
+        assert!((spans[0].exit.distance - 0.7).abs() < 1e-4);
+        assert!((spans[0].enter.normal - Vec3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-4);
+        assert!((spans[0].exit.normal - Vec3::new(1.0, 0.0, 0.0)).magnitude() < 1e-4);
+
+        // Through the diagonal edge shared by the two triangles of the front and back faces
+        let spans = mesh.ray_spans(&Vec3::new(0.5, 0.5, -3.0), &Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].enter.distance - 3.0).abs() < 1e-4);
+        assert!((spans[0].exit.distance - 4.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,247 @@
+// obj.rs
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use nalgebra_glm::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use crate::color::Color;
+use crate::material::Material;
+use crate::texture::Texture;
+use crate::triangle::{Triangle, Vertex};
+
+// Loads a Wavefront OBJ file (and the MTL libraries it references) as a list of
+// triangles. The transform is baked into the vertices so the triangles can go
+// straight into a shared `Mesh`.
+pub fn load_obj(file_path: &str, transform: &Mat4) -> Result<Vec<Triangle>, String> {
+    let source = fs::read_to_string(file_path).map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+    let directory = Path::new(file_path).parent().unwrap_or(Path::new(""));
+    let normal_matrix: Mat3 = nalgebra_glm::mat4_to_mat3(&nalgebra_glm::inverse_transpose(*transform));
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut materials: HashMap<String, Material> = HashMap::new();
+    let mut current_material = Material::material();
+    let mut triangles = Vec::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let p = parse_vec3(&mut tokens);
+                let p = transform * Vec4::new(p.x, p.y, p.z, 1.0);
+                positions.push(p.xyz() / p.w);
+            }
+            Some("vn") => {
+                normals.push((normal_matrix * parse_vec3(&mut tokens)).normalize());
+            }
+            Some("vt") => {
+                let u = tokens.next().map_or(0.0, parse_f32);
+                let v = tokens.next().map_or(0.0, parse_f32);
+                uvs.push(Vec2::new(u, v));
+            }
+            Some("mtllib") => {
+                for library in tokens {
+                    let library_path = directory.join(library);
+                    materials.extend(load_mtl(&library_path)?);
+                }
+            }
+            Some("usemtl") => {
+                let name = tokens.next().unwrap_or("");
+                current_material = materials.get(name).cloned().unwrap_or_else(Material::material);
+            }
+            Some("f") => {
+                let corners: Vec<(usize, Option<usize>, Option<usize>)> = tokens
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("{}:{}: {}", file_path, number + 1, e))?;
+
+                // Polygons are split as a fan around the first corner
+                for i in 1..corners.len().saturating_sub(1) {
+                    let face = [corners[0], corners[i], corners[i + 1]];
+                    let p = face.map(|(position, _, _)| positions[position]);
+                    let flat_normal = (p[1] - p[0]).cross(&(p[2] - p[0])).normalize();
+
+                    let vertices = [0, 1, 2].map(|k| {
+                        let (_, uv, normal) = face[k];
+                        Vertex::new(
+                            p[k],
+                            normal.map_or(flat_normal, |n| normals[n]),
+                            uv.map_or(Vec2::zeros(), |t| uvs[t]),
+                        )
+                    });
+
+                    triangles.push(Triangle::new(vertices, current_material.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(triangles)
+}
+
+// Maps the MTL properties we can represent onto our `Material`:
+// Kd -> diffuse, Ks -> specular albedo, Ns -> specular exponent, Ni -> refractive index,
+// d / Tr -> transparency, Ke -> emission, illum 3+ -> reflectivity, map_Kd / bump -> textures
+fn load_mtl(file_path: &Path) -> Result<HashMap<String, Material>, String> {
+    let source = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to open {}: {}", file_path.display(), e))?;
+    let directory = file_path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, Material)> = None;
+    let mut specular_strength = 0.0;
+
+    for (number, line) in source.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next();
+        // Textures are relative to the MTL file
+        let load_texture = |texture_path: &str| {
+            Texture::load(&directory.join(texture_path).to_string_lossy())
+                .map(Arc::new)
+                .map_err(|message| format!("{}:{}: {}", file_path.display(), number + 1, message))
+        };
+
+        if keyword == Some("newmtl") {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+            let name = tokens.next().unwrap_or("").to_string();
+            current = Some((name, Material::new(Color::new(204, 204, 204), 10.0, [0.8, 0.0, 0.0, 0.0], 1.0)));
+            specular_strength = 0.0;
+            continue;
+        }
+
+        let Some((_, material)) = current.as_mut() else {
+            continue;
+        };
+
+        match keyword {
+            Some("Kd") => material.diffuse = Color::from_vec3(parse_vec3(&mut tokens)),
+            Some("Ks") => {
+                let ks = parse_vec3(&mut tokens);
+                specular_strength = (ks.x + ks.y + ks.z) / 3.0;
+                material.albedo[1] = specular_strength;
+            }
+            Some("Ns") => material.specular = tokens.next().map_or(10.0, parse_f32),
+            Some("Ni") => material.refractive_index = tokens.next().map_or(1.0, parse_f32),
+            Some("d") => material.albedo[3] = 1.0 - tokens.next().map_or(1.0, parse_f32),
+            Some("Tr") => material.albedo[3] = tokens.next().map_or(0.0, parse_f32),
+            Some("Ke") => material.emission = Color::from_vec3(parse_vec3(&mut tokens)),
+            Some("illum") => {
+                let illum = tokens.next().map_or(2, |value| value.parse().unwrap_or(2));
+                if illum >= 3 {
+                    material.albedo[2] = specular_strength;
+                }
+            }
+            Some("map_Kd") => {
+                if let Some(texture_path) = tokens.last() {
+                    material.texture = Some(load_texture(texture_path)?);
+                    material.has_texture = true;
+                }
+            }
+            Some("map_Bump") | Some("bump") | Some("norm") => {
+                if let Some(texture_path) = tokens.last() {
+                    material.normal_map = Some(load_texture(texture_path)?);
+                    material.has_normal_map = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+
+    Ok(materials)
+}
+
+fn parse_f32(token: &str) -> f32 {
+    token.parse().unwrap_or(0.0)
+}
+
+fn parse_vec3<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Vec3 {
+    let x = tokens.next().map_or(0.0, parse_f32);
+    let y = tokens.next().map_or(0.0, parse_f32);
+    let z = tokens.next().map_or(0.0, parse_f32);
+    Vec3::new(x, y, z)
+}
+
+// OBJ indices start at 1 and may be negative (relative to the end of the list).
+// Empty tokens, as in `v//vn`, mean the index is absent.
+fn parse_index(token: &str, count: usize) -> Result<Option<usize>, String> {
+    if token.is_empty() {
+        return Ok(None);
+    }
+
+    let index: i64 = token.parse().map_err(|_| format!("invalid face index {}", token))?;
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("face index {} out of range for {} elements", index, count));
+    }
+    Ok(Some(resolved as usize))
+}
+
+// Parses `v`, `v/vt`, `v//vn` and `v/vt/vn` face corners
+fn parse_corner(corner: &str, positions: usize, uvs: usize, normals: usize) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut parts = corner.split('/');
+    let position = parse_index(parts.next().unwrap_or(""), positions)?
+        .ok_or(format!("missing vertex index in face corner {}", corner))?;
+    let uv = parse_index(parts.next().unwrap_or(""), uvs)?;
+    let normal = parse_index(parts.next().unwrap_or(""), normals)?;
+    Ok((position, uv, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_resolve_relative_and_missing_indices() {
+        assert_eq!(parse_corner("1", 3, 0, 0), Ok((0, None, None)));
+        assert_eq!(parse_corner("2/1", 3, 2, 0), Ok((1, Some(0), None)));
+        assert_eq!(parse_corner("3//2", 3, 0, 2), Ok((2, None, Some(1))));
+        assert_eq!(parse_corner("-1/-2/-1", 3, 2, 2), Ok((2, Some(0), Some(1))));
+    }
+
+    #[test]
+    fn corners_out_of_range_are_errors() {
+        assert!(parse_corner("4", 3, 0, 0).is_err());
+        assert!(parse_corner("0", 3, 0, 0).is_err());
+        assert!(parse_corner("-4", 3, 0, 0).is_err());
+        assert!(parse_corner("1/2", 3, 1, 0).is_err());
+        assert!(parse_corner("x", 3, 0, 0).is_err());
+        assert!(parse_corner("", 3, 0, 0).is_err());
+    }
+
+    #[test]
+    fn quads_are_split_into_triangles() {
+        let file_path = std::env::temp_dir().join("obj_test_quad.obj");
+        fs::write(&file_path, "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").unwrap();
+
+        let triangles = load_obj(&file_path.to_string_lossy(), &Mat4::identity()).unwrap();
+        assert_eq!(triangles.len(), 2);
+    }
+
+    #[test]
+    fn bad_faces_and_missing_files_are_errors() {
+        let file_path = std::env::temp_dir().join("obj_test_bad_face.obj");
+        fs::write(&file_path, "v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+
+        let error = load_obj(&file_path.to_string_lossy(), &Mat4::identity()).err().unwrap();
+        assert!(error.contains(":3:"), "{}", error);
+        assert!(load_obj("does/not/exist.obj", &Mat4::identity()).is_err());
+    }
+
+    #[test]
+    fn missing_textures_are_errors_with_their_line() {
+        let file_path = std::env::temp_dir().join("obj_test_missing_texture.mtl");
+        fs::write(&file_path, "newmtl sign\nKd 1 1 1\nmap_Kd does_not_exist.png\n").unwrap();
+
+        let error = load_mtl(&file_path).err().unwrap();
+        assert!(error.contains(":3:") && error.contains("does_not_exist.png"), "{}", error);
+    }
+}
@@ -0,0 +1,178 @@
+// plane.rs
+
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+// Flat surface shared by `Plane` and `Disc`. Textures repeat every `uv_scale`
+// units; without a texture the surface can fall back to a checker pattern.
+struct Surface {
+    point: Vec3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    material: Material,
+    uv_scale: f32,
+    checker: Option<Color>,
+}
+
+impl Surface {
+    fn new(point: Vec3, normal: Vec3, material: Material) -> Self {
+        let normal = normal.normalize();
+        // Any vector not parallel to the normal works to build the tangent frame
+        let helper = if normal.x.abs() > 0.9 {
+            Vec3::new(0.0, 0.0, 1.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let bitangent = normal.cross(&helper).normalize();
+        let tangent = bitangent.cross(&normal).normalize();
+
+        Surface {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            material,
+            uv_scale: 1.0,
+            checker: None,
+        }
+    }
+
+    fn hit(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        let denom = ray_direction.dot(&self.normal);
+        if denom.abs() < 1e-6 {
+            return None;
+        }
+
+        let t = (self.point - ray_origin).dot(&self.normal) / denom;
+        if t < 0.0 {
+            return None;
+        }
+
+        Some(t)
+    }
+
+    fn intersect_at(&self, hit_point: Vec3, distance: f32) -> Intersect {
+        let local = hit_point - self.point;
+        let u = local.dot(&self.tangent) / self.uv_scale;
+        let v = local.dot(&self.bitangent) / self.uv_scale;
+
+        let mut material = self.material.clone();
+        if let (None, Some(checker)) = (&material.texture, self.checker) {
+            if (u.floor() + v.floor()) as i32 % 2 != 0 {
+                material.diffuse = checker;
+            }
+        }
+
+        Intersect::new(
+            hit_point,
+            self.normal,
+            distance,
+            material,
+            u - u.floor(),
+            v - v.floor(),
+        )
+    }
+}
+
+pub struct Plane {
+    surface: Surface,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Material) -> Self {
+        Plane { surface: Surface::new(point, normal, material) }
+    }
+
+    // Size in world units covered by one repetition of the texture
+    pub fn with_uv_scale(mut self, uv_scale: f32) -> Self {
+        self.surface.uv_scale = uv_scale;
+        self
+    }
+
+    // Alternates the material diffuse with `color` when there is no texture
+    pub fn with_checker(mut self, color: Color) -> Self {
+        self.surface.checker = Some(color);
+        self
+    }
+}
+
+impl RayIntersect for Plane {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match self.surface.hit(ray_origin, ray_direction) {
+            Some(t) => self.surface.intersect_at(ray_origin + ray_direction * t, t),
+            None => Intersect::empty(),
+        }
+    }
+}
+
+pub struct Disc {
+    surface: Surface,
+    radius: f32,
+}
+
+impl Disc {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Material) -> Self {
+        Disc { surface: Surface::new(center, normal, material), radius }
+    }
+
+    pub fn with_uv_scale(mut self, uv_scale: f32) -> Self {
+        self.surface.uv_scale = uv_scale;
+        self
+    }
+}
+
+impl RayIntersect for Disc {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let Some(t) = self.surface.hit(ray_origin, ray_direction) else {
+            return Intersect::empty();
+        };
+
+        let hit_point = ray_origin + ray_direction * t;
+        if (hit_point - self.surface.point).magnitude() > self.radius {
+            return Intersect::empty();
+        }
+
+        self.surface.intersect_at(hit_point, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down(x: f32, z: f32) -> (Vec3, Vec3) {
+        (Vec3::new(x, 5.0, z), Vec3::new(0.0, -1.0, 0.0))
+    }
+
+    #[test]
+    fn checker_alternates_between_neighbouring_cells() {
+        let plane = Plane::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), Material::black())
+            .with_checker(Color::new(255, 255, 255));
+        let diffuse = |x: f32, z: f32| {
+            let (origin, direction) = down(x, z);
+            plane.ray_intersect(&origin, &direction).material.diffuse.to_hex()
+        };
+
+        assert_ne!(diffuse(0.5, 0.5), diffuse(1.5, 0.5));
+        assert_ne!(diffuse(0.5, 0.5), diffuse(0.5, 1.5));
+        // Also across the origin, where the cell coordinates turn negative
+        assert_eq!(diffuse(0.5, 0.5), diffuse(-0.5, -0.5));
+        assert_ne!(diffuse(-0.5, 0.5), diffuse(0.5, 0.5));
+    }
+
+    #[test]
+    fn disc_ends_at_its_radius() {
+        let disc = Disc::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0), 2.0, Material::black());
+        let hits = |x: f32| {
+            let (origin, direction) = down(x, 0.0);
+            disc.ray_intersect(&origin, &direction).is_intersecting
+        };
+
+        assert!(hits(0.0));
+        assert!(hits(1.99));
+        assert!(!hits(2.01));
+    }
+}
@@ -0,0 +1,214 @@
+// polynomial.rs
+//
+// Real roots of low degree polynomials, needed by the curved primitives.
+// Coefficients go from the highest degree down, roots come back unsorted.
+
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(value: f64) -> bool {
+    value.abs() < EPSILON
+}
+
+// a x^2 + b x + c = 0
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if is_zero(a) {
+        if is_zero(b) {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        Vec::new()
+    } else if is_zero(discriminant) {
+        vec![-b / (2.0 * a)]
+    } else {
+        // Avoids cancellation when b is close to the square root
+        let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+        vec![q / a, c / q]
+    }
+}
+
+// a x^3 + b x^2 + c x + d = 0
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if is_zero(a) {
+        return solve_quadratic(b, c, d);
+    }
+
+    // Normal form x^3 + A x^2 + B x + C, then substitute x = y - A/3
+    let (a2, a1, a0) = (b / a, c / a, d / a);
+    let sq_a = a2 * a2;
+    let p = (1.0 / 3.0) * (-(1.0 / 3.0) * sq_a + a1);
+    let q = 0.5 * ((2.0 / 27.0) * a2 * sq_a - (1.0 / 3.0) * a2 * a1 + a0);
+
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let mut roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots
+        let phi = (1.0 / 3.0) * (-q / (-cb_p).sqrt()).acos();
+        let t = 2.0 * (-p).sqrt();
+        vec![
+            t * phi.cos(),
+            -t * (phi + std::f64::consts::PI / 3.0).cos(),
+            -t * (phi - std::f64::consts::PI / 3.0).cos(),
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    let shift = (1.0 / 3.0) * a2;
+    for root in roots.iter_mut() {
+        *root -= shift;
+    }
+    roots
+}
+
+// a x^4 + b x^3 + c x^2 + d x + e = 0 (Ferrari)
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if is_zero(a) {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // Normal form x^4 + A x^3 + B x^2 + C x + D, then substitute x = y - A/4
+    let (a3, a2, a1, a0) = (b / a, c / a, d / a, e / a);
+    let sq_a = a3 * a3;
+    let p = -(3.0 / 8.0) * sq_a + a2;
+    let q = (1.0 / 8.0) * sq_a * a3 - 0.5 * a3 * a2 + a1;
+    let r = -(3.0 / 256.0) * sq_a * sq_a + (1.0 / 16.0) * sq_a * a2 - 0.25 * a3 * a1 + a0;
+
+    let mut roots = if is_zero(r) {
+        // y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic(1.0, 0.0, p, q);
+        roots.push(0.0);
+        roots
+    } else {
+        // Resolvent cubic, any real root works
+        let z = solve_cubic(1.0, -0.5 * p, -r, 0.5 * r * p - (1.0 / 8.0) * q * q)[0];
+
+        let mut u = z * z - r;
+        let mut v = 2.0 * z - p;
+
+        if is_zero(u) {
+            u = 0.0;
+        } else if u > 0.0 {
+            u = u.sqrt();
+        } else {
+            return Vec::new();
+        }
+
+        if is_zero(v) {
+            v = 0.0;
+        } else if v > 0.0 {
+            v = v.sqrt();
+        } else {
+            return Vec::new();
+        }
+
+        let mut roots = solve_quadratic(1.0, if q < 0.0 { -v } else { v }, z - u);
+        roots.extend(solve_quadratic(1.0, if q < 0.0 { v } else { -v }, z + u));
+        roots
+    };
+
+    let shift = 0.25 * a3;
+    for root in roots.iter_mut() {
+        *root -= shift;
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+        roots.sort_by(|a, b| a.total_cmp(b));
+        roots
+    }
+
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        let roots = sorted(roots);
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-6, "{:?} != {:?}", roots, expected);
+        }
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        // (x - 1)(x - 3)
+        assert_roots(solve_quadratic(1.0, -4.0, 3.0), &[1.0, 3.0]);
+        assert_roots(solve_quadratic(1.0, -2.0, 1.0), &[1.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+        // Degenerates to 2x - 4
+        assert_roots(solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn cubic_with_three_real_roots() {
+        // (x + 2)(x - 1)(x - 4)
+        assert_roots(solve_cubic(1.0, -3.0, -6.0, 8.0), &[-2.0, 1.0, 4.0]);
+        // Leading coefficient other than 1: 2(x - 0.5)(x - 1.5)(x - 2.5)
+        assert_roots(solve_cubic(2.0, -9.0, 11.5, -3.75), &[0.5, 1.5, 2.5]);
+    }
+
+    #[test]
+    fn cubic_with_one_real_root() {
+        // (x - 2)(x² + 1)
+        assert_roots(solve_cubic(1.0, -2.0, 1.0, -2.0), &[2.0]);
+    }
+
+    #[test]
+    fn cubic_with_repeated_roots() {
+        // (x - 1)²(x + 2)
+        assert_roots(solve_cubic(1.0, 0.0, -3.0, 2.0), &[-2.0, 1.0]);
+        // (x - 3)³
+        assert_roots(solve_cubic(1.0, -9.0, 27.0, -27.0), &[3.0]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x + 3)(x + 1)(x - 2)(x - 5)
+        assert_roots(solve_quartic(1.0, -3.0, -15.0, 19.0, 30.0), &[-3.0, -1.0, 2.0, 5.0]);
+        // Biquadratic (x² - 1)(x² - 4) scaled by 3
+        assert_roots(solve_quartic(3.0, 0.0, -15.0, 0.0, 12.0), &[-2.0, -1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_with_two_or_no_real_roots() {
+        // (x - 1)(x - 2)(x² + 1)
+        assert_roots(solve_quartic(1.0, -3.0, 3.0, -3.0, 2.0), &[1.0, 2.0]);
+        // (x² + 1)(x² + 4)
+        assert_roots(solve_quartic(1.0, 0.0, 5.0, 0.0, 4.0), &[]);
+    }
+
+    #[test]
+    fn quartic_with_a_zero_root() {
+        // x (x - 1)(x + 1)(x - 2)
+        assert_roots(solve_quartic(1.0, -2.0, -1.0, 2.0, 0.0), &[-1.0, 0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic_degenerates_to_cubic() {
+        assert_roots(solve_quartic(0.0, 1.0, -3.0, -6.0, 8.0), &[-2.0, 1.0, 4.0]);
+    }
+
+    #[test]
+    fn quartic_of_a_ray_through_a_torus() {
+        // Ray along x through a torus of radii 2 and 0.5 in the xz plane: crosses at ±1.5 and ±2.5.
+        // (x² + R² - r²)² - 4R²x² with R = 2, r = 0.5
+        let (big, small) = (2.0f64, 0.5f64);
+        let k = big * big - small * small;
+        let roots = solve_quartic(1.0, 0.0, 2.0 * k - 4.0 * big * big, 0.0, k * k);
+        assert_roots(roots, &[-2.5, -1.5, 1.5, 2.5]);
+    }
+}
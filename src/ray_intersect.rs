@@ -1,8 +1,23 @@
 // ray_intersect.rs
 
+use std::cell::Cell;
 use nalgebra_glm::Vec3;
 use crate::material::Material;
 
+// Rays traced while shading one sample, counted for the ray cost view
+#[derive(Default)]
+pub struct RayCount(Cell<u32>);
+
+impl RayCount {
+    pub fn add(&self, rays: u32) {
+        self.0.set(self.0.get() + rays);
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0.get()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Intersect {
     pub point: Vec3,
@@ -40,6 +55,79 @@ impl Intersect {
     }
 }
 
-pub trait RayIntersect {
+// Send + Sync so scenes can be traced from several rayon threads
+pub trait RayIntersect: Send + Sync {
   fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
-}
\ No newline at end of file
+
+  // Whether the object encloses a volume. Open surfaces such as planes, discs,
+  // single triangles or meshes with holes have no inside.
+  fn is_solid(&self) -> bool {
+      false
+  }
+
+  // Intervals along the ray that lie inside the solid, sorted by distance and
+  // including those behind the origin. Only solids report them: `Csg` refuses
+  // any other operand when it is built, so this is never reached for the rest.
+  fn ray_spans(&self, _ray_origin: &Vec3, _ray_direction: &Vec3) -> Vec<Span> {
+      unreachable!("ray_spans called on an open surface")
+  }
+}
+
+// Where a ray enters and leaves a solid. Both normals point out of the solid.
+#[derive(Debug, Clone)]
+pub struct Span {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
+impl Span {
+    pub fn new(enter: Intersect, exit: Intersect) -> Self {
+        Span { enter, exit }
+    }
+}
+
+// Lightweight hit record used by primitives that find several surface crossings
+// along a ray, before the closest one is turned into a full `Intersect`
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceHit {
+    pub distance: f32,
+    pub normal: Vec3,
+    pub u: f32,
+    pub v: f32,
+}
+
+impl SurfaceHit {
+    pub fn new(distance: f32, normal: Vec3, u: f32, v: f32) -> Self {
+        SurfaceHit { distance, normal, u, v }
+    }
+
+    pub fn to_intersect(self, ray_origin: &Vec3, ray_direction: &Vec3, material: &Material) -> Intersect {
+        Intersect::new(
+            ray_origin + ray_direction * self.distance,
+            self.normal,
+            self.distance,
+            material.clone(),
+            self.u,
+            self.v,
+        )
+    }
+}
+
+// Closest hit in front of the ray origin
+pub fn first_hit(hits: &[SurfaceHit]) -> Option<SurfaceHit> {
+    hits.iter()
+        .filter(|hit| hit.distance > 0.0)
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        .copied()
+}
+
+// Pairs up the sorted crossings of a closed surface into inside intervals.
+// A grazing ray can report an odd number of crossings, the leftover one is dropped.
+pub fn spans_from_hits(hits: &[SurfaceHit], ray_origin: &Vec3, ray_direction: &Vec3, material: &Material) -> Vec<Span> {
+    hits.chunks_exact(2)
+        .map(|pair| Span::new(
+            pair[0].to_intersect(ray_origin, ray_direction, material),
+            pair[1].to_intersect(ray_origin, ray_direction, material),
+        ))
+        .collect()
+}
@@ -0,0 +1,156 @@
+// render_settings.rs
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stereo {
+    Off,
+    // Left eye in the red channel and right eye in green and blue, for red/cyan glasses
+    Anaglyph,
+    // Left eye on the left half of the image and right eye on the right half, for headsets
+    SideBySide,
+}
+
+impl Stereo {
+    pub fn next(self) -> Stereo {
+        match self {
+            Stereo::Off => Stereo::Anaglyph,
+            Stereo::Anaglyph => Stereo::SideBySide,
+            Stereo::SideBySide => Stereo::Off,
+        }
+    }
+}
+
+// What `render` writes for every pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    // Full lighting, shadows, reflections and refractions
+    Shaded,
+    // The rest show a single value from the first hit, without lighting
+    Normal,
+    Depth,
+    Uv,
+    Albedo,
+    MaterialId,
+    // How many rays each pixel needed, from blue (few) to red (many)
+    RayCost,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 7] = [
+        RenderMode::Shaded,
+        RenderMode::Normal,
+        RenderMode::Depth,
+        RenderMode::Uv,
+        RenderMode::Albedo,
+        RenderMode::MaterialId,
+        RenderMode::RayCost,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Shaded => "shaded",
+            RenderMode::Normal => "normal",
+            RenderMode::Depth => "depth",
+            RenderMode::Uv => "uv",
+            RenderMode::Albedo => "albedo",
+            RenderMode::MaterialId => "material",
+            RenderMode::RayCost => "cost",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        RenderMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn next(self) -> RenderMode {
+        let index = RenderMode::ALL.iter().position(|mode| *mode == self).unwrap();
+        RenderMode::ALL[(index + 1) % RenderMode::ALL.len()]
+    }
+}
+
+// Light added to every surface besides the direct light, darkened in corners and crevices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ambient {
+    Off,
+    // Rays traced around every hit, open within `ao_radius`
+    Occlusion,
+    // Minecraft-style corners darkened by the neighbouring blocks, much cheaper
+    Smooth,
+}
+
+impl Ambient {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ambient::Off => "off",
+            Ambient::Occlusion => "occlusion",
+            Ambient::Smooth => "smooth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Ambient> {
+        [Ambient::Off, Ambient::Occlusion, Ambient::Smooth]
+            .into_iter()
+            .find(|ambient| ambient.name() == name)
+    }
+
+    pub fn next(self) -> Ambient {
+        match self {
+            Ambient::Off => Ambient::Occlusion,
+            Ambient::Occlusion => Ambient::Smooth,
+            Ambient::Smooth => Ambient::Off,
+        }
+    }
+}
+
+// Options for `render` that are not part of the scene or the camera
+pub struct RenderSettings {
+    // Rays traced per pixel and averaged. Needed for depth of field, and
+    // more than one also smooths the edges of the blocks.
+    pub samples: u32,
+    pub mode: RenderMode,
+    pub stereo: Stereo,
+    // Distance between the two eyes in world units
+    pub interocular_distance: f32,
+    // Distance in front of the camera where both eyes meet, objects there
+    // appear at the depth of the screen
+    pub convergence_distance: f32,
+    pub ambient: Ambient,
+    // Strength of the ambient light on an open surface
+    pub ambient_intensity: f32,
+    // How far occluders are searched for and how many rays per hit, for `Ambient::Occlusion`
+    pub ao_radius: f32,
+    pub ao_samples: u32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            samples: 1,
+            mode: RenderMode::Shaded,
+            stereo: Stereo::Off,
+            interocular_distance: 0.3,
+            convergence_distance: 15.0,
+            ambient: Ambient::Off,
+            ambient_intensity: 0.3,
+            ao_radius: 1.0,
+            ao_samples: 8,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn change_samples(&mut self, delta: i32) {
+        self.samples = (self.samples as i32 + delta).clamp(1, 64) as u32;
+    }
+
+    pub fn change_interocular_distance(&mut self, delta: f32) {
+        self.interocular_distance = (self.interocular_distance + delta).clamp(0.0, 2.0);
+    }
+
+    pub fn change_convergence_distance(&mut self, delta: f32) {
+        self.convergence_distance = (self.convergence_distance + delta).clamp(1.0, 100.0);
+    }
+
+    pub fn change_ambient_intensity(&mut self, delta: f32) {
+        self.ambient_intensity = (self.ambient_intensity + delta).clamp(0.0, 2.0);
+    }
+}
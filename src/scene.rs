@@ -0,0 +1,137 @@
+// scene.rs
+
+use std::sync::Arc;
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use crate::environment::Environment;
+use crate::fog::Fog;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::world::{palette, Block, World};
+
+// The parts of one prop, like the stick and flame of a torch, which share an object ID
+pub type Prop = Vec<Box<dyn RayIntersect>>;
+
+// Everything that can be hit by a ray: the editable block world and any other
+// geometry placed around it
+pub struct Scene {
+    pub world: World,
+    pub palette: Vec<(&'static str, Arc<Material>)>,
+    // Cubes built from `world`, rebuilt after every edit, with the ID of their block
+    blocks: Vec<(u32, Box<dyn RayIntersect>)>,
+    // Meshes, primitives and floors that are not part of the block grid, with the
+    // ID of their prop
+    props: Vec<(u32, Box<dyn RayIntersect>)>,
+    // Seen by rays that miss everything
+    pub environment: Environment,
+    // Participating medium between the camera and the surfaces
+    pub fog: Fog,
+    // Lights standing for emissive props, placed by the diorama. Surfaces already
+    // show their glow through emission, so only the fog is lit by them.
+    pub glows: Vec<Light>,
+    // The same for emissive blocks, rebuilt with them
+    block_glows: Vec<Light>,
+}
+
+impl Scene {
+    // Props are numbered from 1 in the order given and blocks after them, so
+    // editing the world never changes the ID of a prop
+    pub fn new(world: World, props: Vec<Prop>) -> Self {
+        let props = props
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, parts)| parts.into_iter().map(move |part| (index as u32 + 1, part)))
+            .collect();
+
+        let mut scene = Scene {
+            world,
+            palette: palette(),
+            blocks: Vec::new(),
+            props,
+            environment: Environment::default(),
+            fog: Fog::default(),
+            glows: Vec::new(),
+            block_glows: Vec::new(),
+        };
+        scene.rebuild_blocks();
+        scene
+    }
+
+    pub fn set_world(&mut self, world: World) {
+        self.world = world;
+        self.rebuild_blocks();
+    }
+
+    // Needed after changing `world` so rays see the edits
+    pub fn rebuild_blocks(&mut self) {
+        let first_id = self.props.last().map_or(1, |(id, _)| id + 1);
+        self.blocks = self
+            .world
+            .build(&self.palette)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, parts)| parts.into_iter().map(move |part| (first_id + index as u32, part)))
+            .collect();
+
+        self.block_glows = self
+            .world
+            .iter()
+            .filter_map(|((x, y, z), block)| {
+                let emission = self.palette[block.material as usize - 1].1.emission;
+                let center = Vec3::new(x as f32, y as f32, z as f32) + Vec3::repeat(0.5);
+                (!emission.is_black()).then(|| Light::glow(center, emission))
+            })
+            .collect();
+    }
+
+    // One of the glows of the props and blocks chosen at random, with how many
+    // there are to choose from
+    pub fn random_glow(&self, rng: &mut impl Rng) -> Option<(&Light, usize)> {
+        let count = self.glows.len() + self.block_glows.len();
+        if count == 0 {
+            return None;
+        }
+        let index = rng.gen_range(0..count);
+        let glow = self.glows.get(index).unwrap_or_else(|| &self.block_glows[index - self.glows.len()]);
+        Some((glow, count))
+    }
+
+    // Removes the block a ray hit. Returns false if the hit was not on a block.
+    pub fn remove_block_at(&mut self, intersect: &Intersect) -> bool {
+        // Step slightly into the surface to land inside the block
+        let cell = World::cell_at(&(intersect.point - intersect.normal * 0.01));
+        if self.world.remove(cell).is_none() {
+            return false;
+        }
+        self.rebuild_blocks();
+        true
+    }
+
+    // Places a block in the free cell in front of the face a ray hit
+    pub fn place_block_at(&mut self, intersect: &Intersect, block: Block) -> bool {
+        let cell = World::cell_at(&(intersect.point + intersect.normal * 0.01));
+        if self.world.get(cell).is_some() {
+            return false;
+        }
+        self.world.set(cell, block);
+        self.rebuild_blocks();
+        true
+    }
+
+    // Whether a ray hit one of the blocks rather than a prop
+    pub fn is_block_hit(&self, intersect: &Intersect) -> bool {
+        let cell = World::cell_at(&(intersect.point - intersect.normal * 0.01));
+        self.world.get(cell).is_some()
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Box<dyn RayIntersect>> {
+        self.blocks.iter().chain(self.props.iter()).map(|(_, object)| object)
+    }
+
+    // Every object with the ID of the block or prop it is part of
+    pub fn par_objects(&self) -> impl ParallelIterator<Item = (u32, &Box<dyn RayIntersect>)> {
+        self.blocks.par_iter().chain(self.props.par_iter()).map(|(id, object)| (*id, object))
+    }
+}
@@ -0,0 +1,186 @@
+// sky.rs
+
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+
+// Scales the sky luminance of the model, in kcd/m², to display values
+const SKY_EXPOSURE: f32 = 0.07;
+// Angular radius of the sun disk in radians, larger than the real one so it shows up
+const SUN_RADIUS: f32 = 0.03;
+const SUN_DISK_BRIGHTNESS: f32 = 20.0;
+// Below the horizon the sky is reflected by a dark ground
+const GROUND_FACTOR: f32 = 0.3;
+const NIGHT_COLOR: Vec3 = Vec3::new(0.01, 0.012, 0.03);
+// Latitude the sun path is computed for, it passes south of the zenith at noon
+const LATITUDE: f32 = 0.6;
+
+// Preetham et al. "A Practical Analytic Model for Daylight" (1999). The sky
+// color comes from the position of the sun and the turbidity (haze) of the air.
+#[derive(Debug, Clone, Copy)]
+pub struct Sky {
+    // Hours from 0 to 24, sunrise at 6 and sunset at 18
+    pub time: f32,
+    // 2 is a very clear sky, 10 a hazy one
+    pub turbidity: f32,
+}
+
+impl Sky {
+    pub fn new(time: f32, turbidity: f32) -> Self {
+        Sky {
+            time: time.rem_euclid(24.0),
+            turbidity: turbidity.clamp(1.5, 10.0),
+        }
+    }
+
+    pub fn advance(&mut self, hours: f32) {
+        self.time = (self.time + hours).rem_euclid(24.0);
+    }
+
+    // Unit vector towards the sun. It rises in +x and sets in -x, leaning towards -z.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = (self.time - 6.0) / 12.0 * PI;
+        Vec3::new(angle.cos(), angle.sin() * LATITUDE.cos(), -angle.sin() * LATITUDE.sin()).normalize()
+    }
+
+    // 1 during the day, fading to 0 while the sun goes below the horizon
+    pub fn daylight(&self) -> f32 {
+        smoothstep(-0.1, 0.05, self.sun_direction().y)
+    }
+
+    // Color of direct sunlight after crossing the atmosphere, brightest channel at 1.
+    // Rayleigh and aerosol transmittance of the model at red, green and blue wavelengths.
+    pub fn sun_color(&self) -> Vec3 {
+        let sun = self.sun_direction();
+        let zenith_angle = sun.y.max(0.0).acos();
+        // Relative optical mass of air the light goes through
+        let degrees = zenith_angle.to_degrees().min(93.0);
+        let air_mass = 1.0 / (zenith_angle.cos() + 0.15 * (93.885 - degrees).powf(-1.253));
+
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |wavelength: f32| {
+            let rayleigh = (-0.008735 * wavelength.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * wavelength.powf(-1.3) * air_mass).exp();
+            rayleigh * aerosol
+        };
+
+        // Wavelengths in micrometers
+        let color = Vec3::new(transmittance(0.65), transmittance(0.57), transmittance(0.475));
+        color / color.max().max(1e-6)
+    }
+
+    // Radiance seen looking towards `direction`. The sun disk is left out with
+    // `sun_disk` false, for when a separate light already stands for the sun.
+    pub fn sample(&self, direction: &Vec3, sun_disk: bool) -> Vec3 {
+        let direction = direction.normalize();
+        let sun = self.sun_direction();
+
+        // The model only covers a sun above the horizon, lower ones reuse the sunset
+        let sun_zenith = sun.y.clamp(0.02, 1.0).acos();
+        let view_zenith = direction.y.max(0.001).acos();
+        let gamma = direction.dot(&sun).clamp(-1.0, 1.0).acos();
+
+        let t = self.turbidity;
+        let luminance = zenith_luminance(t, sun_zenith) * perez_ratio(&luminance_coefficients(t), view_zenith, gamma, sun_zenith);
+        let x = zenith_x(t, sun_zenith) * perez_ratio(&x_coefficients(t), view_zenith, gamma, sun_zenith);
+        let y = zenith_y(t, sun_zenith) * perez_ratio(&y_coefficients(t), view_zenith, gamma, sun_zenith);
+
+        let mut color = xyy_to_rgb(x, y, luminance * SKY_EXPOSURE);
+        if sun_disk && gamma < SUN_RADIUS && direction.y > 0.0 {
+            color += self.sun_color() * SUN_DISK_BRIGHTNESS;
+        }
+        if direction.y < 0.0 {
+            color *= GROUND_FACTOR;
+        }
+
+        let daylight = self.daylight();
+        color * daylight + NIGHT_COLOR * (1.0 - daylight)
+    }
+}
+
+// Distribution of a sky value relative to the zenith, F(θ, γ) / F(0, θs)
+fn perez_ratio(coefficients: &[f32; 5], view_zenith: f32, gamma: f32, sun_zenith: f32) -> f32 {
+    let perez = |zenith: f32, gamma: f32| {
+        let [a, b, c, d, e] = *coefficients;
+        (1.0 + a * (b / zenith.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+    };
+    perez(view_zenith, gamma) / perez(0.0, sun_zenith)
+}
+
+fn luminance_coefficients(t: f32) -> [f32; 5] {
+    [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703]
+}
+
+fn x_coefficients(t: f32) -> [f32; 5] {
+    [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452]
+}
+
+fn y_coefficients(t: f32) -> [f32; 5] {
+    [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529]
+}
+
+fn zenith_luminance(t: f32, sun_zenith: f32) -> f32 {
+    let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+    (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192
+}
+
+fn zenith_x(t: f32, s: f32) -> f32 {
+    let (s2, s3) = (s * s, s * s * s);
+    t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+        + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+        + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886)
+}
+
+fn zenith_y(t: f32, s: f32) -> f32 {
+    let (s2, s3) = (s * s, s * s * s);
+    t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+        + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+        + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688)
+}
+
+// CIE xyY chromaticity and luminance to linear sRGB
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Vec3::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+    .map(|channel| channel.max(0.0))
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_rises_in_the_east_and_sets_in_the_west() {
+        let direction = |time: f32| Sky::new(time, 3.0).sun_direction();
+
+        assert!((direction(6.0) - Vec3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((direction(18.0) - Vec3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+        let noon = direction(12.0);
+        assert!(noon.x.abs() < 1e-5 && noon.y > 0.5 && noon.z < 0.0, "{:?}", noon);
+        assert!((noon.magnitude() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sun_disk_can_be_left_out() {
+        let sky = Sky::new(12.0, 3.0);
+        let sun = sky.sun_direction();
+
+        let with_disk = sky.sample(&sun, true);
+        let without_disk = sky.sample(&sun, false);
+        assert!((with_disk - without_disk - sky.sun_color() * SUN_DISK_BRIGHTNESS).magnitude() < 1e-3);
+
+        // Away from the sun both are the plain sky
+        let zenith = Vec3::new(0.0, 1.0, 0.0);
+        assert_eq!(sky.sample(&zenith, true), sky.sample(&zenith, false));
+    }
+}
@@ -13,12 +13,16 @@ pub struct Texture {
 }
 
 impl Texture {
+    // Textures the program ships with, a missing one is a broken install
     pub fn new(file_path: &str) -> Self {
-        // Attempt to load the image file
+        Texture::load(file_path).unwrap_or_else(|message| panic!("{}", message))
+    }
+
+    pub fn load(file_path: &str) -> Result<Self, String> {
         let img = ImageReader::open(file_path)
-            .expect("Failed to open texture file") // Panic if the file is not accessible
+            .map_err(|e| format!("Failed to open texture {}: {}", file_path, e))?
             .decode()
-            .expect("Failed to decode texture file"); // Panic if the image format is invalid
+            .map_err(|e| format!("Failed to decode texture {}: {}", file_path, e))?;
 
         let width = img.width() as usize;
         let height = img.height() as usize;
@@ -32,11 +36,11 @@ impl Texture {
             }
         }
 
-        Texture {
+        Ok(Texture {
             width,
             height,
             color_array,
-        }
+        })
     }
 
     pub fn get_color(&self, x: usize, y: usize) -> Color {
@@ -0,0 +1,118 @@
+// torus.rs
+
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{first_hit, spans_from_hits, Intersect, RayIntersect, Span, SurfaceHit};
+use crate::material::Material;
+use crate::polynomial::solve_quartic;
+
+// Torus lying on the xz plane around `center`. `major_radius` goes from the
+// center to the middle of the tube and `minor_radius` is the tube radius.
+pub struct Torus {
+    pub center: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Material,
+}
+
+impl Torus {
+    pub fn new(center: Vec3, major_radius: f32, minor_radius: f32, material: Material) -> Self {
+        Torus { center, major_radius, minor_radius, material }
+    }
+
+    fn normal_at(&self, p: &Vec3) -> Vec3 {
+        // On the axis of a spindle torus every point of the ring is equally close
+        let axis_distance = (p.x * p.x + p.z * p.z).sqrt();
+        if axis_distance < 1e-6 {
+            return Vec3::new(0.0, if p.y < 0.0 { -1.0 } else { 1.0 }, 0.0);
+        }
+
+        let ring = Vec3::new(p.x, 0.0, p.z) / axis_distance * self.major_radius;
+        (p - ring).normalize()
+    }
+
+    fn uv_at(&self, p: &Vec3) -> (f32, f32) {
+        let u = 0.5 + p.z.atan2(p.x) / (2.0 * PI);
+        let distance_xz = (p.x * p.x + p.z * p.z).sqrt();
+        let v = 0.5 + p.y.atan2(distance_xz - self.major_radius) / (2.0 * PI);
+        (u, v)
+    }
+
+    // Every crossing of the surface along the ray, including those behind the origin
+    pub fn hits(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<SurfaceHit> {
+        // Solved in f64 with a unit direction, the quartic is badly conditioned otherwise
+        let o = (ray_origin - self.center).cast::<f64>();
+        let length = ray_direction.magnitude() as f64;
+        let d = ray_direction.cast::<f64>() / length;
+
+        let major2 = (self.major_radius * self.major_radius) as f64;
+        let minor2 = (self.minor_radius * self.minor_radius) as f64;
+        let four_major2 = 4.0 * major2;
+
+        let e = o.dot(&o) - major2 - minor2;
+        let f = o.dot(&d);
+
+        let mut hits: Vec<SurfaceHit> = solve_quartic(
+            1.0,
+            4.0 * f,
+            2.0 * e + 4.0 * f * f + four_major2 * d.y * d.y,
+            4.0 * f * e + 2.0 * four_major2 * o.y * d.y,
+            e * e - four_major2 * (minor2 - o.y * o.y),
+        )
+        .into_iter()
+        .map(|t| {
+            let t = (t / length) as f32;
+            let p = ray_origin - self.center + ray_direction * t;
+            let (u, v) = self.uv_at(&p);
+            SurfaceHit::new(t, self.normal_at(&p), u, v)
+        })
+        .collect();
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+}
+
+impl RayIntersect for Torus {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match first_hit(&self.hits(ray_origin, ray_direction)) {
+            Some(hit) => hit.to_intersect(ray_origin, ray_direction, &self.material),
+            None => Intersect::empty(),
+        }
+    }
+
+    fn is_solid(&self) -> bool {
+        true
+    }
+
+    fn ray_spans(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Vec<Span> {
+        spans_from_hits(&self.hits(ray_origin, ray_direction), ray_origin, ray_direction, &self.material)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_on_the_axis_is_finite() {
+        // A spindle torus, with the tube wider than the ring, crosses its own axis
+        let torus = Torus::new(Vec3::zeros(), 0.5, 1.0, Material::black());
+
+        assert_eq!(torus.normal_at(&Vec3::new(0.0, 0.8, 0.0)), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(torus.normal_at(&Vec3::new(0.0, -0.8, 0.0)), Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn ray_crosses_the_tube_twice_on_each_side() {
+        let torus = Torus::new(Vec3::zeros(), 2.0, 0.5, Material::black());
+        let hits = torus.hits(&Vec3::new(-5.0, 0.0, 0.0), &Vec3::new(1.0, 0.0, 0.0));
+
+        let distances: Vec<f32> = hits.iter().map(|hit| hit.distance).collect();
+        assert_eq!(distances.len(), 4, "{:?}", distances);
+        for (distance, expected) in distances.iter().zip([2.5, 3.5, 6.5, 7.5]) {
+            assert!((distance - expected).abs() < 1e-4, "{:?}", distances);
+        }
+        assert!((hits[0].normal - Vec3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-4);
+    }
+}
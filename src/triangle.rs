@@ -0,0 +1,102 @@
+// triangle.rs
+
+use nalgebra_glm::{Vec2, Vec3};
+use crate::bvh::Aabb;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::material::Material;
+
+const EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+}
+
+impl Vertex {
+    pub fn new(position: Vec3, normal: Vec3, uv: Vec2) -> Self {
+        Vertex { position, normal, uv }
+    }
+}
+
+pub struct Triangle {
+    pub vertices: [Vertex; 3],
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vertex; 3], material: Material) -> Self {
+        Triangle { vertices, material }
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for vertex in &self.vertices {
+            bounds.grow(&vertex.position);
+        }
+        bounds
+    }
+
+    // Möller–Trumbore, returns (t, u, v) with u and v the barycentrics of vertices 1 and 2
+    pub fn hit(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32, f32)> {
+        self.crossing(ray_origin, ray_direction).filter(|(t, _, _)| *t >= EPSILON)
+    }
+
+    // Like `hit`, but also behind the ray origin
+    pub fn crossing(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32, f32)> {
+        let [v0, v1, v2] = &self.vertices;
+        let edge1 = v1.position - v0.position;
+        let edge2 = v2.position - v0.position;
+
+        let p = ray_direction.cross(&edge2);
+        let det = edge1.dot(&p);
+
+        // Ray is parallel to the triangle
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = ray_origin - v0.position;
+        let u = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(&edge1);
+        let v = ray_direction.dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        Some((edge2.dot(&q) * inv_det, u, v))
+    }
+
+    // Builds the full intersect once we know this triangle is the closest hit
+    pub fn intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, t: f32, u: f32, v: f32) -> Intersect {
+        let [v0, v1, v2] = &self.vertices;
+        let w = 1.0 - u - v;
+
+        let normal = (v0.normal * w + v1.normal * u + v2.normal * v).normalize();
+        let uv = v0.uv * w + v1.uv * u + v2.uv * v;
+
+        Intersect::new(
+            ray_origin + ray_direction * t,
+            normal,
+            t,
+            self.material.clone(),
+            uv.x,
+            uv.y,
+        )
+    }
+}
+
+impl RayIntersect for Triangle {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match self.hit(ray_origin, ray_direction) {
+            Some((t, u, v)) => self.intersect_at(ray_origin, ray_direction, t, u, v),
+            None => Intersect::empty(),
+        }
+    }
+}
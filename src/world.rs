@@ -0,0 +1,289 @@
+// world.rs
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Arc;
+use nalgebra_glm::Vec3;
+use crate::block_shape::{BlockShape, Facing};
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::ray_intersect::RayIntersect;
+
+// Materials blocks can be made of, a block stores its 1-based index in this list.
+// Every block built from an entry shares its material.
+pub fn palette() -> Vec<(&'static str, Arc<Material>)> {
+    let palette = vec![
+        ("dirt", Material::dirt()),                     // 1
+        ("obsidian", Material::obsidian()),             // 2
+        ("crying obsidian", Material::crying_osidian()), // 3
+        ("glass", Material::glass()),                   // 4
+        ("diamond ore", Material::diamond_ore()),       // 5
+        ("beacon", Material::beacon()),                 // 6
+        ("crafting table", Material::crafting_table()), // 7
+        ("tnt", Material::tnt()),                       // 8
+        ("cobblestone", Material::cobblestone()),       // 9
+        ("bookshelf", Material::bookshelf()),           // 10
+        ("redstone lamp", Material::redstone_lamp()),   // 11
+    ];
+    palette.into_iter().map(|(name, material)| (name, Arc::new(material))).collect()
+}
+
+// Integer coordinates (x, y, z) of a unit cell, the minimum corner of its block
+pub type Cell = (i32, i32, i32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Block {
+    pub material: u8, // Index into `palette`, starting at 1
+    pub shape: BlockShape,
+}
+
+impl Block {
+    pub fn new(material: u8, shape: BlockShape) -> Self {
+        Block { material, shape }
+    }
+}
+
+// Editable grid of unit blocks. Kept sorted by cell so the blocks are always
+// built, and numbered, in the same order.
+#[derive(Default)]
+pub struct World {
+    blocks: BTreeMap<Cell, Block>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        World::default()
+    }
+
+    pub fn get(&self, cell: Cell) -> Option<Block> {
+        self.blocks.get(&cell).copied()
+    }
+
+    pub fn set(&mut self, cell: Cell, block: Block) {
+        self.blocks.insert(cell, block);
+    }
+
+    pub fn remove(&mut self, cell: Cell) -> Option<Block> {
+        self.blocks.remove(&cell)
+    }
+
+    // Every block in cell order
+    pub fn iter(&self) -> impl Iterator<Item = (Cell, Block)> + '_ {
+        self.blocks.iter().map(|(&cell, &block)| (cell, block))
+    }
+
+    // Cell that contains a point
+    pub fn cell_at(point: &Vec3) -> Cell {
+        (point.x.floor() as i32, point.y.floor() as i32, point.z.floor() as i32)
+    }
+
+    // Adds a layer of material indices (0 for air) at height `y`. `shapes` holds
+    // one row string per layer row with a `BlockShape` code per cell; missing
+    // rows or cells are full blocks.
+    pub fn add_layer(&mut self, y: i32, layer: &[[u8; 8]], shapes: &[&str]) {
+        for (z, row) in layer.iter().enumerate() {
+            for (x, &material) in row.iter().enumerate() {
+                if material == 0 {
+                    continue;
+                }
+
+                let code = shapes.get(z).and_then(|row| row.chars().nth(x));
+                let shape = code.and_then(BlockShape::from_char).unwrap_or(BlockShape::Full);
+                self.set((x as i32, y, z as i32), Block::new(material, shape));
+            }
+        }
+    }
+
+    // The cubes of every block, one list per block in cell order
+    pub fn build(&self, palette: &[(&str, Arc<Material>)]) -> Vec<Vec<Box<dyn RayIntersect>>> {
+        let mut objects = Vec::new();
+
+        for (&(x, y, z), block) in &self.blocks {
+            let cell_min = Vec3::new(x as f32, y as f32, z as f32);
+            let cell_max = cell_min + Vec3::new(1.0, 1.0, 1.0);
+            let material = &palette[(block.material - 1) as usize].1;
+
+            // Which horizontal neighbours a fence or pane should reach out to
+            let connections = Facing::ALL.map(|facing| {
+                let (dx, dz) = facing.offset();
+                self.get((x + dx, y, z + dz)).is_some_and(|neighbour| block.shape.connects_to(neighbour.shape))
+            });
+
+            // Textured as the whole block, so partial shapes show the matching part of it
+            let mut parts: Vec<Box<dyn RayIntersect>> = Vec::new();
+            for (min, max) in block.shape.boxes(connections) {
+                let cube = Cube::new(cell_min + min, cell_min + max, Arc::clone(material))
+                    .with_uv_bounds(cell_min, cell_max);
+                parts.push(Box::new(cube));
+            }
+            objects.push(parts);
+        }
+
+        objects
+    }
+
+    // Scene format: an `origin x z` line, then a `layer y` line per height followed
+    // by one row per z and one token per x, counted from the origin. A token is the
+    // palette index (0 for air) with an optional shape code, like `9` or `2_`.
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        let mut source = String::from("# Diorama world\n");
+
+        if let Some((min, max)) = self.bounds() {
+            source += &format!("origin {} {}\n", min.0, min.2);
+
+            for y in min.1..=max.1 {
+                if !self.blocks.keys().any(|cell| cell.1 == y) {
+                    continue;
+                }
+
+                source += &format!("\nlayer {}\n", y);
+                for z in min.2..=max.2 {
+                    let row: Vec<String> = (min.0..=max.0)
+                        .map(|x| match self.get((x, y, z)) {
+                            Some(Block { material, shape: BlockShape::Full }) => material.to_string(),
+                            Some(block) => format!("{}{}", block.material, block.shape.to_char()),
+                            None => String::from("0"),
+                        })
+                        .collect();
+                    source += &row.join(" ");
+                    source += "\n";
+                }
+            }
+        }
+
+        fs::write(file_path, source).map_err(|e| format!("Failed to save {}: {}", file_path, e))
+    }
+
+    pub fn load(file_path: &str, palette_size: usize) -> Result<World, String> {
+        let source = fs::read_to_string(file_path).map_err(|e| format!("Failed to open {}: {}", file_path, e))?;
+
+        let mut world = World::new();
+        let mut origin = (0, 0);
+        let mut layer: Option<(i32, i32)> = None; // Height and next row
+
+        for (number, line) in source.lines().enumerate() {
+            let error = |message: &str| format!("{}:{}: {}", file_path, number + 1, message);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            match words.clone().next() {
+                Some("origin") => {
+                    let values: Vec<i32> = words.skip(1).map(|w| w.parse().map_err(|_| error("invalid origin"))).collect::<Result<_, _>>()?;
+                    if values.len() != 2 {
+                        return Err(error("expected `origin x z`"));
+                    }
+                    origin = (values[0], values[1]);
+                }
+                Some("layer") => {
+                    let y = words.nth(1).and_then(|w| w.parse().ok()).ok_or_else(|| error("expected `layer y`"))?;
+                    layer = Some((y, 0));
+                }
+                _ => {
+                    let Some((y, row)) = layer.as_mut() else {
+                        return Err(error("row outside of a layer"));
+                    };
+
+                    for (x, token) in words.enumerate() {
+                        let digits = token.find(|c: char| !c.is_ascii_digit()).unwrap_or(token.len());
+                        let material: u8 = token[..digits].parse().map_err(|_| error("invalid block"))?;
+                        if material as usize > palette_size {
+                            return Err(error(&format!("unknown material {}", material)));
+                        }
+
+                        let shape = match &token[digits..] {
+                            "" => BlockShape::Full,
+                            code => {
+                                let mut chars = code.chars();
+                                match (chars.next().and_then(BlockShape::from_char), chars.next()) {
+                                    (Some(shape), None) => shape,
+                                    _ => return Err(error(&format!("unknown shape {}", code))),
+                                }
+                            }
+                        };
+
+                        if material != 0 {
+                            world.set((origin.0 + x as i32, *y, origin.1 + *row), Block::new(material, shape));
+                        }
+                    }
+                    *row += 1;
+                }
+            }
+        }
+
+        Ok(world)
+    }
+
+    // Smallest and largest occupied cell on each axis
+    fn bounds(&self) -> Option<(Cell, Cell)> {
+        let mut cells = self.blocks.keys();
+        let first = *cells.next()?;
+
+        Some(cells.fold((first, first), |(min, max), cell| {
+            (
+                (min.0.min(cell.0), min.1.min(cell.1), min.2.min(cell.2)),
+                (max.0.max(cell.0), max.1.max(cell.1), max.2.max(cell.2)),
+            )
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut world = World::new();
+        world.set((-2, 0, 3), Block::new(1, BlockShape::Full));
+        world.set((0, 0, 0), Block::new(9, BlockShape::Stairs(Facing::East)));
+        world.set((1, 0, 1), Block::new(4, BlockShape::Pane));
+        world.set((1, 3, -1), Block::new(11, BlockShape::TopSlab));
+        world.set((3, 3, 2), Block::new(2, BlockShape::Fence));
+
+        let file_path = temp_path("world_round_trip.scene");
+        world.save(&file_path).unwrap();
+        let loaded = World::load(&file_path, palette().len()).unwrap();
+
+        assert_eq!(loaded.blocks, world.blocks);
+    }
+
+    #[test]
+    fn build_keeps_the_parts_of_a_block_together() {
+        let mut world = World::new();
+        world.set((0, 0, 0), Block::new(9, BlockShape::Stairs(Facing::East)));
+        world.set((2, 0, 0), Block::new(1, BlockShape::Full));
+
+        let blocks = world.build(&palette());
+        assert_eq!(blocks.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+    }
+
+    #[test]
+    fn empty_world_round_trip() {
+        let file_path = temp_path("world_empty.scene");
+        World::new().save(&file_path).unwrap();
+        assert!(World::load(&file_path, palette().len()).unwrap().blocks.is_empty());
+    }
+
+    #[test]
+    fn invalid_worlds_are_errors() {
+        let cases = [
+            ("world_unknown_material.scene", "layer 0\n1 99\n"),
+            ("world_unknown_shape.scene", "layer 0\n1 2?\n"),
+            ("world_row_outside_layer.scene", "1 2\n"),
+            ("world_bad_origin.scene", "origin 1\nlayer 0\n1\n"),
+        ];
+
+        for (name, source) in cases {
+            let file_path = temp_path(name);
+            fs::write(&file_path, source).unwrap();
+            assert!(World::load(&file_path, palette().len()).is_err(), "{}", name);
+        }
+    }
+}